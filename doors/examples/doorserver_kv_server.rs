@@ -0,0 +1,42 @@
+//! A key-value store whose state lives in a `DoorServer`, rather than in a
+//! `static mut`.
+
+use doors::server::{DoorServer, Request, Response, Serve};
+use std::sync::atomic::{AtomicU8, Ordering};
+
+const INCREMENT: u8 = 0;
+const FETCH: u8 = 1;
+
+struct Counter {
+    count: AtomicU8,
+}
+
+impl Serve for Counter {
+    type Data<'a> = [u8; 1];
+
+    fn serve(&self, request: Request<'_, Self>) -> Response<[u8; 1]> {
+        match request.data.first() {
+            Some(&INCREMENT) => {
+                self.count.fetch_add(1, Ordering::SeqCst);
+                Response::new([0])
+            }
+            Some(&FETCH) => Response::new([self.count.load(Ordering::SeqCst)]),
+            _ => Response::new([u8::MAX]),
+        }
+    }
+}
+
+fn main() {
+    let counter = Counter {
+        count: AtomicU8::new(0),
+    };
+    let server = DoorServer::new(counter, "/tmp/doorserver_kv.door").unwrap();
+
+    let handle = server.shutdown_handle();
+    std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_secs(5));
+        handle.shutdown();
+    });
+
+    server.run();
+}
@@ -1,17 +1,34 @@
-//! A Barebones server using only the illumos headers, and no additional
-//! support. This helps validate that the headers are expressed correctly in
-//! Rust.
+//! A server built with the `server_procedure` macro, which doubles the byte it
+//! is sent. Despite the file's name, nothing is capitalized here: this is the
+//! server for the `procmac_double` tests.
+//!
+//! Rather than sleep for a fixed time, it serves until told to stop: an empty
+//! request asks it to shut down, and so does a timer, after five seconds.
 
-use doors::server::{Door, Request, Response};
+use doors::server::{Door, Request, Response, Shutdown};
+use std::time::Duration;
+
+static SHUTDOWN: Shutdown = Shutdown::new();
 
 #[doors::server_procedure]
 fn double(payload: Request<'_>) -> Response<[u8; 1]> {
-    Response::new([payload.data[0] * 2])
+    if payload.data.is_empty() {
+        SHUTDOWN.request();
+    }
+    match payload.data_array() {
+        Some([n]) => Response::new([n * 2]),
+        None => Response::empty(),
+    }
 }
 
 fn main() {
     let door = Door::create(double).unwrap();
     door.force_install("/tmp/procmac_double.door").unwrap();
 
-    std::thread::sleep(std::time::Duration::from_secs(5));
+    std::thread::spawn(|| {
+        std::thread::sleep(Duration::from_secs(5));
+        SHUTDOWN.request();
+    });
+    door.wait_for_shutdown("/tmp/procmac_double.door", &SHUTDOWN)
+        .unwrap();
 }
@@ -0,0 +1,35 @@
+//! Serve a configuration document straight out of the door's state. Each call
+//! returns the bytes where they lie in the state, so nothing is copied or
+//! leaked, and no `static mut` buffer is needed.
+
+use doors::server::{Door, Request, Response};
+
+struct Config {
+    rendered: Vec<u8>,
+}
+
+impl Config {
+    fn new(settings: &[(&str, &str)]) -> Self {
+        let mut rendered = Vec::new();
+        for (key, value) in settings {
+            rendered
+                .extend_from_slice(format!("{}={}\n", key, value).as_bytes());
+        }
+        Self { rendered }
+    }
+}
+
+#[doors::server_procedure(state = Config)]
+fn config(x: Request<'_, Config>) -> Response<&[u8]> {
+    Response::from_state(&x, |state| &state.rendered)
+}
+
+fn main() {
+    let settings = [("listen", "0.0.0.0:80"), ("workers", "4")];
+    let state = Box::leak(Box::new(Config::new(&settings)));
+
+    let door = Door::create_with_state(config, state).unwrap();
+    door.force_install("/tmp/procmac_config.door").unwrap();
+
+    std::thread::sleep(std::time::Duration::from_secs(5));
+}
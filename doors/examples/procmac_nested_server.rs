@@ -0,0 +1,33 @@
+//! A door whose server procedure calls another door in the same process.
+
+use doors::server::{Door, Request, Response};
+use doors::Client;
+use doors::DoorArgument;
+
+#[doors::server_procedure]
+fn increment(x: Request<'_>) -> Response<[u8; 1]> {
+    Response::new([x.data[0] + 1])
+}
+
+#[doors::server_procedure]
+fn increment_then_double(x: Request<'_>) -> Response<[u8; 1]> {
+    let inner = Client::open("/tmp/procmac_nested_inner.door").unwrap();
+    let mut rbuf = [0];
+    let arg = DoorArgument::new(x.data, &[], &mut rbuf);
+    let incremented = inner.call(arg).unwrap().data()[0];
+    Response::new([incremented * 2])
+}
+
+fn main() {
+    let inner = Door::create(increment).unwrap();
+    inner
+        .force_install("/tmp/procmac_nested_inner.door")
+        .unwrap();
+
+    let outer = Door::create(increment_then_double).unwrap();
+    outer
+        .force_install("/tmp/procmac_nested_outer.door")
+        .unwrap();
+
+    std::thread::sleep(std::time::Duration::from_secs(5));
+}
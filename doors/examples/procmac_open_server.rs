@@ -2,22 +2,39 @@
 //! support. This helps validate that the headers are expressed correctly in
 //! Rust.
 
-use doors::server::{Door, Request, Response};
+use doors::server::{Request, Response};
 use std::ffi::CStr;
 use std::fs::File;
 use std::os::fd::IntoRawFd;
 
-#[doors::server_procedure]
+#[doors::server_procedure(install = "/tmp/procmac_open_server.door")]
 fn open_file(x: Request<'_>) -> Response<[u8; 0]> {
+    let txt_path = match CStr::from_bytes_with_nul(x.data).map(CStr::to_str) {
+        Ok(Ok(txt_path)) => txt_path,
+        _ => x.reply_error(libc::EINVAL as u32),
+    };
+    let file = match File::open(txt_path) {
+        Ok(file) => file,
+        Err(e) => x.reply_error(e.raw_os_error().unwrap_or(libc::EIO) as u32),
+    };
+    Response::empty().add_descriptor(file.into_raw_fd(), true)
+}
+
+#[doors::server_procedure(install = "/tmp/procmac_open_twice.door")]
+fn open_file_twice(x: Request<'_>) -> Response<[u8; 0]> {
     let txt_path_cstring = CStr::from_bytes_with_nul(x.data).unwrap();
     let txt_path = txt_path_cstring.to_str().unwrap();
-    let file = File::open(txt_path).unwrap();
-    Response::empty().add_descriptor(file.into_raw_fd(), true)
+    let first = File::open(txt_path).unwrap();
+    let second = File::open(txt_path).unwrap();
+    Response::with_descriptors(
+        [],
+        &[(first.into_raw_fd(), true), (second.into_raw_fd(), true)],
+    )
 }
 
 fn main() {
-    let door = Door::create(open_file).unwrap();
-    door.force_install("/tmp/procmac_open_server.door").unwrap();
+    let _door = install_open_file().unwrap();
+    let _twice = install_open_file_twice().unwrap();
 
     std::thread::sleep(std::time::Duration::from_secs(5));
 }
@@ -0,0 +1,34 @@
+//! Hold on to a descriptor passed by one client, and hand it to another.
+
+use doors::server::{Door, Request, Response};
+use std::os::fd::{IntoRawFd, OwnedFd};
+use std::sync::Mutex;
+
+static STASH: Mutex<Vec<OwnedFd>> = Mutex::new(Vec::new());
+
+#[doors::server_procedure]
+fn stash(x: Request<'_>) -> Response<[u8; 0]> {
+    let fd = x.dup_descriptor(0).unwrap();
+    STASH.lock().unwrap().push(fd);
+    Response::empty()
+}
+
+#[doors::server_procedure]
+fn fetch(_x: Request<'_>) -> Response<[u8; 0]> {
+    let fd = STASH.lock().unwrap().pop().unwrap();
+    Response::empty().add_descriptor(fd.into_raw_fd(), true)
+}
+
+fn main() {
+    let stash_door = Door::create(stash).unwrap();
+    stash_door
+        .force_install("/tmp/procmac_stash_store.door")
+        .unwrap();
+
+    let fetch_door = Door::create(fetch).unwrap();
+    fetch_door
+        .force_install("/tmp/procmac_stash_fetch.door")
+        .unwrap();
+
+    std::thread::sleep(std::time::Duration::from_secs(5));
+}
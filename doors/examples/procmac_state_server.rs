@@ -0,0 +1,26 @@
+//! Share a counter between invocations through the door's cookie, rather than
+//! through a `static mut`.
+
+use doors::server::{Door, Request, Response};
+use std::sync::atomic::{AtomicU8, Ordering};
+
+struct Counter {
+    count: AtomicU8,
+}
+
+#[doors::server_procedure(state = Counter)]
+fn increment(x: Request<'_, Counter>) -> Response<[u8; 1]> {
+    let previous = x.state.count.fetch_add(1, Ordering::SeqCst);
+    Response::new([previous + 1])
+}
+
+fn main() {
+    let counter = Box::leak(Box::new(Counter {
+        count: AtomicU8::new(0),
+    }));
+
+    let door = Door::create_with_state(increment, counter).unwrap();
+    door.force_install("/tmp/procmac_state.door").unwrap();
+
+    std::thread::sleep(std::time::Duration::from_secs(5));
+}
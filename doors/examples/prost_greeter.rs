@@ -0,0 +1,59 @@
+//! A door which speaks Protocol Buffers, and a client which calls it.
+//!
+//! Requests and replies are length-delimited protobuf messages, so either end
+//! could just as well be written in another language. Run it with
+//! `cargo run --example prost_greeter --features prost`.
+
+use doors::server::{Door, Request, Response};
+use doors::Client;
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct HelloRequest {
+    #[prost(string, tag = "1")]
+    name: String,
+    #[prost(uint32, tag = "2")]
+    times: u32,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct HelloReply {
+    #[prost(string, repeated, tag = "1")]
+    greetings: Vec<String>,
+}
+
+/// Error code for requests which are not a `HelloRequest`.
+const BAD_REQUEST: u32 = 1;
+
+#[doors::server_procedure]
+fn greet(x: Request<'_>) -> Response<Vec<u8>> {
+    let request: HelloRequest = match x.message() {
+        Ok(request) => request,
+        Err(e) => {
+            return Response::err_bytes(BAD_REQUEST, e.to_string().as_bytes())
+        }
+    };
+    let greetings = (0..request.times)
+        .map(|i| format!("hello #{}, {}", i + 1, request.name))
+        .collect();
+    Response::message(&HelloReply { greetings })
+}
+
+fn main() {
+    let door = Door::create(greet).unwrap();
+    door.force_install("/tmp/prost_greeter.door").unwrap();
+
+    let client = Client::open("/tmp/prost_greeter.door").unwrap();
+    let request = HelloRequest {
+        name: String::from("doors"),
+        times: 3,
+    };
+    let reply: HelloReply = client.call_message(&request).unwrap();
+    for greeting in reply.greetings {
+        println!("{}", greeting);
+    }
+
+    // Anything else is answered with an error frame.
+    let garbage = client.call_with_data(&[0xff; 4]).unwrap();
+    let error = garbage.as_result().unwrap_err();
+    assert_eq!(error.code, BAD_REQUEST);
+}
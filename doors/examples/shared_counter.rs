@@ -0,0 +1,52 @@
+//! Two processes counting through one door, over state in shared memory.
+//!
+//! The parent creates the counter in a `MAP_SHARED` region, and a door which
+//! increments it, then forks. Both processes call the door -- which is always
+//! served by the parent, its creator -- and the child also bumps the counter
+//! directly, through the shared region. Since several processes touch the
+//! counter at once, it is an atomic.
+
+use doors::server::{Door, Request, Response, Shared};
+use doors::Client;
+use std::os::fd::{AsRawFd, FromRawFd};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+#[doors::server_procedure(state = Shared<AtomicU64>)]
+fn increment(x: Request<'_, Shared<AtomicU64>>) -> Response<[u8; 8]> {
+    let count = x.shared_state().fetch_add(1, Ordering::SeqCst) + 1;
+    Response::new(count.to_le_bytes())
+}
+
+/// Call the door `n` times.
+fn count(client: &Client, n: usize) {
+    for _ in 0..n {
+        client.call_with_data(&[]).unwrap();
+    }
+}
+
+fn main() {
+    // Safety: an atomic holds no pointers.
+    let counter = unsafe { Shared::new(AtomicU64::new(0)) }.unwrap();
+    let door = Door::create_with_state(increment, counter).unwrap();
+
+    // Both processes inherit this descriptor.
+    let fd = unsafe { libc::dup(door.as_raw_fd()) };
+    let client = unsafe { Client::from_raw_fd(fd) };
+
+    match unsafe { libc::fork() } {
+        -1 => panic!("fork failed"),
+        0 => {
+            count(&client, 3);
+            counter.fetch_add(10, Ordering::SeqCst);
+            unsafe { libc::_exit(0) }
+        }
+        child => {
+            count(&client, 3);
+            let mut status = 0;
+            unsafe { libc::waitpid(child, &mut status, 0) };
+            let total = counter.load(Ordering::SeqCst);
+            println!("both processes counted to {}", total);
+            assert_eq!(total, 16);
+        }
+    }
+}
@@ -0,0 +1,72 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ *
+ * Copyright 2023 Robert D. French
+ */
+//! A deadline for every door call a thread makes.
+//!
+//! This module is only available with the `ambient-deadline` feature enabled.
+//! Frameworks which give each request a time budget often keep it in a
+//! thread-local, rather than passing it to every function. Setting the
+//! deadline here has [`Client::call`][crate::Client::call],
+//! [`Client::call_timed`][crate::Client::call_timed], and everything built on
+//! them, fail straight away with
+//! [`DoorCallError::TimedOut`][crate::DoorCallError::TimedOut] once the
+//! deadline has passed, rather than making a call whose answer nobody will be
+//! around to use.
+//!
+//! Only calls which have not started yet are affected. A call which starts
+//! before the deadline runs to completion; see
+//! [`Client::call_deadline`][crate::Client::call_deadline] for one which is
+//! abandoned part way through.
+//!
+//! ```
+//! use std::time::{Duration, Instant};
+//!
+//! let budget = Instant::now() + Duration::from_millis(250);
+//! doors::deadline::with(budget, || {
+//!     // Door calls made here give up once the budget is spent.
+//! });
+//! assert_eq!(doors::deadline::get(), None);
+//! ```
+
+use std::cell::Cell;
+use std::time::Instant;
+
+thread_local! {
+    static DEADLINE: Cell<Option<Instant>> = const { Cell::new(None) };
+}
+
+/// Set this thread's deadline, or clear it with `None`.
+pub fn set(deadline: Option<Instant>) {
+    DEADLINE.with(|d| d.set(deadline))
+}
+
+/// This thread's deadline, if it has one.
+pub fn get() -> Option<Instant> {
+    DEADLINE.with(|d| d.get())
+}
+
+/// Run `f` with this thread's deadline set to `deadline`, then put back
+/// whatever deadline was there before.
+pub fn with<T>(deadline: Instant, f: impl FnOnce() -> T) -> T {
+    /// Restores the previous deadline, even if `f` panics.
+    struct Restore(Option<Instant>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            set(self.0)
+        }
+    }
+
+    let _restore = Restore(get());
+    set(Some(deadline));
+    f()
+}
+
+/// Whether this thread's deadline has already passed.
+pub(crate) fn expired() -> bool {
+    get().is_some_and(|deadline| Instant::now() >= deadline)
+}
@@ -0,0 +1,342 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ *
+ * Copyright 2023 Robert D. French
+ */
+//! Explanations of this crate's errors, for the people who have to fix them.
+//!
+//! The error types say *what* went wrong, in the kernel's terms. An operator
+//! reading a log usually wants to know *why*, and what to do about it. The
+//! [`explain`] function answers with a few lines: what happened, what most
+//! often causes it, and what to try.
+//!
+//! ```
+//! use doors::Client;
+//!
+//! let client = Client::open("/etc/passwd").unwrap();
+//! if let Err(e) = client.call_with_data(b"hello") {
+//!     eprintln!("{}", doors::explain(&e));
+//! }
+//! ```
+//!
+//! Since `/etc/passwd` is no door, this prints:
+//!
+//! ```text
+//! NotADoor: the descriptor is open, but is not a door
+//!   likely cause: the client was pointed at the wrong path, or the ...
+//!   try: check the path, and that the server is running
+//! ```
+
+use crate::illumos;
+use crate::server;
+use crate::DoorCallError;
+
+/// An error which [`explain`] can describe.
+pub trait Explain {
+    /// What happened, its likely cause, and what to try, over several lines.
+    fn explain(&self) -> String;
+}
+
+/// Explain `err` to an operator, over several lines.
+///
+/// The first line names the error and says what happened. The next says what
+/// most often causes it, and the last what to try. Errors which wrap others,
+/// like [`server::Error::AttachDoor`], say which step failed and then explain
+/// the error underneath.
+pub fn explain<E: Explain + ?Sized>(err: &E) -> String {
+    err.explain()
+}
+
+/// Lay out the three parts of an explanation.
+fn lines(summary: &str, cause: &str, remedy: &str) -> String {
+    format!("{summary}\n  likely cause: {cause}\n  try: {remedy}")
+}
+
+impl Explain for DoorCallError {
+    fn explain(&self) -> String {
+        match self {
+            Self::E2BIG => lines(
+                "E2BIG: the arguments were too big for a server thread's stack",
+                "the request carried more data than a door thread can hold",
+                "send less data per call, or have the server raise its thread \
+                 stack size",
+            ),
+            Self::EAGAIN => lines(
+                "EAGAIN: the server was out of resources",
+                "every server thread was busy, or the server could not start \
+                 another",
+                "retry after a short wait; if it persists, give the server a \
+                 bigger thread pool",
+            ),
+            Self::EBADF => lines(
+                "EBADF: the door descriptor is invalid",
+                "the descriptor was closed, the server may have exited, or the \
+                 path is not a door",
+                "reopen the door, and check that its server is running",
+            ),
+            Self::EFAULT => lines(
+                "EFAULT: an argument pointed outside the address space",
+                "a buffer or door_arg_t was built by hand and is wrong",
+                "this is a bug in the caller; check any raw calls",
+            ),
+            Self::EINTR => lines(
+                "EINTR: the call was interrupted",
+                "a signal reached the client, the client forked, or the server \
+                 exited mid-call",
+                "retry the call; if the server exited, reopen the door first",
+            ),
+            Self::EINVAL => lines(
+                "EINVAL: bad arguments were passed to door_call",
+                "a malformed request, which is a bug in the caller",
+                "check any raw calls and the descriptors being sent",
+            ),
+            Self::EMFILE => lines(
+                "EMFILE: too many open descriptors",
+                "the client or server descriptor table is full, often from \
+                 descriptors received and never closed",
+                "close unused descriptors, or raise RLIMIT_NOFILE",
+            ),
+            Self::ENFILE => lines(
+                "ENFILE: too many descriptors for this door",
+                "the request carried more descriptors than the door's \
+                 DOOR_PARAM_DESC_MAX allows",
+                "send fewer descriptors per call; Client::params shows the \
+                 limit",
+            ),
+            Self::ENOBUFS => lines(
+                "ENOBUFS: the request is the wrong size for this door",
+                "more data than DOOR_PARAM_DATA_MAX or less than \
+                 DOOR_PARAM_DATA_MIN",
+                "check Client::params, and use Client::call_chunked for large \
+                 payloads",
+            ),
+            Self::ENOTSUP => lines(
+                "ENOTSUP: the door refuses descriptors",
+                "descriptors were sent to a door created with DOOR_REFUSE_DESC",
+                "send the request without descriptors",
+            ),
+            Self::EOVERFLOW => lines(
+                "EOVERFLOW: no room could be mapped for the results",
+                "the client is short of memory or address space",
+                "retry later, or free memory in the client",
+            ),
+            Self::DescriptorsRefused => lines(
+                "DescriptorsRefused: the door refuses descriptors",
+                "descriptors were sent to a door created with \
+                 DOOR_REFUSE_DESC, so the call was not made",
+                "send the request without descriptors",
+            ),
+            Self::NotADoor => lines(
+                "NotADoor: the descriptor is open, but is not a door",
+                "the client was pointed at the wrong path, or the server has \
+                 yet to install its door there",
+                "check the path, and that the server is running",
+            ),
+            Self::Revoked => lines(
+                "Revoked: the server revoked this door",
+                "the server shut down or replaced the door",
+                "reopen the door by its path to reach the new server",
+            ),
+            Self::ResponseTooLarge(size) => lines(
+                &format!(
+                    "ResponseTooLarge: the server answered with {size} bytes"
+                ),
+                "the response was bigger than the limit set with \
+                 Client::with_max_response",
+                "raise the limit if the server is trusted, or ask for less",
+            ),
+            Self::TruncatedFrame(needed) => lines(
+                &format!(
+                    "TruncatedFrame: the response is shorter than its \
+                     {needed} byte frame"
+                ),
+                "the server does not answer with [len][payload] frames, or \
+                 cut its answer short",
+                "check that the server builds its answers with \
+                 Response::framed",
+            ),
+            Self::Cancelled => lines(
+                "Cancelled: the call was abandoned with a CancelHandle",
+                "something in this process asked for it",
+                "nothing, unless the cancellation was unexpected",
+            ),
+            Self::WouldDeadlock => lines(
+                "WouldDeadlock: a handler tried to call the door it is serving",
+                "the server calls itself, directly or through a helper",
+                "do the work in the handler directly, or on another thread",
+            ),
+            Self::TimedOut => lines(
+                "TimedOut: the server did not answer before the deadline",
+                "the server is slow or overloaded",
+                "retry, or allow more time for the call",
+            ),
+            Self::Unknown(errno) => lines(
+                &format!("Unknown: door_call failed with errno {errno}"),
+                "the kernel returned an error door_call(3C) does not document",
+                "look the errno up in errno.h for this system",
+            ),
+        }
+    }
+}
+
+impl Explain for illumos::Error {
+    fn explain(&self) -> String {
+        match self {
+            Self::EACCES => lines(
+                "EACCES: permission denied",
+                "the path is not writable by this user, or is locked",
+                "check the path's permissions, or install the door elsewhere",
+            ),
+            Self::EBADF => lines(
+                "EBADF: the descriptor is not valid",
+                "it was closed, or never referred to a door",
+                "check that the door was created and has not been revoked",
+            ),
+            Self::EBUSY => lines(
+                "EBUSY: the path is busy",
+                "another door is already attached there, or it is a mount \
+                 point",
+                "use Door::force_install, or detach the old door first",
+            ),
+            Self::EINVAL => lines(
+                "EINVAL: invalid arguments",
+                "the path is on a remote filesystem, nothing is attached to \
+                 it, or the door attributes were bad",
+                "install the door on a local filesystem, and check its \
+                 attributes",
+            ),
+            Self::ELOOP => lines(
+                "ELOOP: too many symbolic links",
+                "the path contains a symbolic link loop",
+                "resolve the links in the path",
+            ),
+            Self::EMFILE => lines(
+                "EMFILE: too many open descriptors",
+                "the process descriptor table is full",
+                "close unused descriptors, or raise RLIMIT_NOFILE",
+            ),
+            Self::ENAMETOOLONG => lines(
+                "ENAMETOOLONG: the path is too long",
+                "the path exceeds PATH_MAX, or a component exceeds NAME_MAX",
+                "use a shorter path",
+            ),
+            Self::ENOENT => lines(
+                "ENOENT: the path does not exist",
+                "the file, or a directory above it, has not been created",
+                "create the file first, or use Door::install_with_parents",
+            ),
+            Self::ENOTDIR => lines(
+                "ENOTDIR: part of the path is not a directory",
+                "a file sits where the path expects a directory",
+                "check each component of the path",
+            ),
+            Self::EPERM => lines(
+                "EPERM: operation not permitted",
+                "this user does not own the path and lacks the privilege to \
+                 attach over it",
+                "run as the path's owner, or install the door elsewhere",
+            ),
+            Self::EFAULT => lines(
+                "EFAULT: bad address",
+                "a pointer handed to the kernel was invalid",
+                "this is a bug in the caller",
+            ),
+            Self::EAGAIN => lines(
+                "EAGAIN: resources were temporarily unavailable",
+                "the system could not create a door or thread just now",
+                "retry after a short wait",
+            ),
+            Self::ENOMEM => lines(
+                "ENOMEM: not enough memory",
+                "the system or process is short of memory",
+                "free memory, or retry later",
+            ),
+            Self::DoorGone => lines(
+                "DoorGone: the door this thread is bound to no longer exists",
+                "the door was revoked while the thread waited for calls",
+                "let the thread exit, or bind it to another door",
+            ),
+            Self::NulInPath => lines(
+                "NulInPath: the path contains a NUL byte",
+                "the path was built from untrusted or binary data",
+                "remove the NUL byte from the path",
+            ),
+            Self::Unknown(errno) => lines(
+                &format!("Unknown: the call failed with errno {errno}"),
+                "the kernel returned an error its man page does not document",
+                "look the errno up in errno.h for this system",
+            ),
+        }
+    }
+}
+
+impl Explain for server::Error {
+    fn explain(&self) -> String {
+        use server::Error;
+
+        let (step, io) = match self {
+            Error::InvalidPath(_) => {
+                return lines(
+                    "InvalidPath: the path contains a NUL byte",
+                    "the path was built from untrusted or binary data",
+                    "remove the NUL byte from the path",
+                )
+            }
+            Error::DoorCall(errno) => {
+                return lines(
+                    &format!("DoorCall: door_call failed with errno {errno}"),
+                    "the door's server is down, or refused the request",
+                    "check that the server is running, then retry",
+                )
+            }
+            Error::AttachDoor(e) => {
+                return nested("AttachDoor: attaching the door failed", e)
+            }
+            Error::CreateDoor(e) => {
+                return nested("CreateDoor: creating the door failed", e)
+            }
+            Error::BindThread(e) => {
+                return nested(
+                    "BindThread: binding a thread to the private door failed",
+                    e,
+                )
+            }
+            Error::DetachDoor(e) => {
+                return nested("DetachDoor: detaching the door failed", e)
+            }
+            Error::QueryDoor(e) => {
+                return nested("QueryDoor: asking about the door failed", e)
+            }
+            Error::InstallJamb(e) => {
+                ("InstallJamb: creating the file to attach to failed", e)
+            }
+            Error::OpenDoor(e) => ("OpenDoor: opening the door failed", e),
+            Error::Cloexec(e) => {
+                ("Cloexec: marking the door close-on-exec failed", e)
+            }
+            Error::SpawnThread(e) => {
+                ("SpawnThread: starting a server thread failed", e)
+            }
+            Error::CreateParent(e) => (
+                "CreateParent: creating the door's parent directories failed",
+                e,
+            ),
+            Error::CloneDoor(e) => {
+                ("CloneDoor: duplicating the door descriptor failed", e)
+            }
+        };
+        lines(
+            &format!("{step}: {io}"),
+            "the operating system refused; the error above says why",
+            "fix the condition named above (permissions, a missing \
+             directory, descriptor limits), then try again",
+        )
+    }
+}
+
+/// Say which step failed, then explain the error underneath.
+fn nested(step: &str, e: &illumos::Error) -> String {
+    format!("{step}, because:\n{}", e.explain())
+}
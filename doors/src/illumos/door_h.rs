@@ -64,6 +64,28 @@ pub type door_server_procedure_t = extern "C" fn(
     n_desc: libc::c_uint,
 );
 
+/// Create a thread for the private pool of a door made with [`door_xcreate`].
+///
+/// The new thread must run `start(arg)`, which binds it to the door and waits
+/// for invocations. Return 1 if a thread was created, 0 if none was needed,
+/// or -1 if creating one failed. `crcookie` is whatever was handed to
+/// [`door_xcreate`].
+///
+/// See [`DOOR_XCREATE(3C)`] for more details.
+///
+/// [`DOOR_XCREATE(3C)`]: https://illumos.org/man/3c/door_xcreate
+pub type door_xcreate_server_func_t = extern "C" fn(
+    info: *mut door_info_t,
+    start: extern "C" fn(*mut libc::c_void) -> *mut libc::c_void,
+    arg: *mut libc::c_void,
+    crcookie: *mut libc::c_void,
+) -> libc::c_int;
+
+/// Prepare a thread created by a [`door_xcreate_server_func_t`], from within
+/// that thread, before it binds itself to the door.
+pub type door_xcreate_thrsetup_func_t =
+    extern "C" fn(crcookie: *mut libc::c_void);
+
 extern "C" {
     /// Turns a function into a file descriptor.
     ///
@@ -73,6 +95,12 @@ extern "C" {
     /// applications are free to set `attributes` to
     /// [DOOR_REFUSE_DESC](constant.DOOR_REFUSE_DESC.html).
     ///
+    /// This is the raw, unchecked escape hatch: nothing stops it from being
+    /// handed a pointer which is not really a server procedure, and the kernel
+    /// will happily jump to it on the next call. Prefer
+    /// [`illumos::door_create`][crate::illumos::door_create] or
+    /// [`Door::create`][crate::server::Door::create].
+    ///
     /// See [`DOOR_CREATE(3C)`] for more details.
     ///
     /// [1]: type.door_server_procedure_t.html
@@ -83,6 +111,23 @@ extern "C" {
         attributes: door_attr_t,
     ) -> libc::c_int;
 
+    /// Create a door with its own private pool of server threads, which are
+    /// made by `thr_create_func` -- `nthread` of them right away, and more
+    /// whenever the pool runs dry.
+    ///
+    /// See [`DOOR_XCREATE(3C)`] for more details.
+    ///
+    /// [`DOOR_XCREATE(3C)`]: https://illumos.org/man/3c/door_xcreate
+    pub fn door_xcreate(
+        server_procedure: door_server_procedure_t,
+        cookie: *const libc::c_void,
+        attributes: door_attr_t,
+        thr_create_func: door_xcreate_server_func_t,
+        thr_setup_func: Option<door_xcreate_thrsetup_func_t>,
+        crcookie: *mut libc::c_void,
+        nthread: libc::c_int,
+    ) -> libc::c_int;
+
     /// Invoke a function in another process.
     ///
     /// Assuming `d` is a descriptor for a door which points to a function in
@@ -99,8 +144,10 @@ extern "C" {
     ///
     /// Use this at the end of `server_procedure` in lieu of the traditional
     /// `return` statement to transfer control back to the process which
-    /// originally issued `door_call`. Like [`EXECVE(2)`], this function is
-    /// terminal from the perspective of the code which calls it.
+    /// originally issued `door_call`. Like [`EXECVE(2)`], this function does
+    /// not return when it succeeds. It only comes back, with `-1` and `errno`
+    /// set, if the results could not be delivered or the thread cannot wait for
+    /// another invocation.
     ///
     /// See [`DOOR_RETURN(3C)`].
     ///
@@ -120,7 +167,7 @@ extern "C" {
         data_size: libc::size_t,
         desc_ptr: *const door_desc_t,
         num_desc: libc::c_uint,
-    ) -> !;
+    ) -> libc::c_int;
 
     /// Return information associated with a door descriptor
     ///
@@ -165,6 +212,61 @@ extern "C" {
     ///
     /// [`DOOR_REVOKE(3C)`]: https://illumos.org/man/3c/door_revoke
     pub fn door_revoke(d: libc::c_int) -> libc::c_int;
+
+    /// Bind the calling thread to a door's private server pool.
+    ///
+    /// The door must have been created with [`DOOR_PRIVATE`]. Once bound, the
+    /// thread will only ever be handed invocations of that door, which it waits
+    /// for by calling [`door_return`].
+    ///
+    /// See [`DOOR_BIND(3C)`] for more details.
+    ///
+    /// [`DOOR_BIND(3C)`]: https://illumos.org/man/3c/door_bind
+    pub fn door_bind(d: libc::c_int) -> libc::c_int;
+
+    /// Release the calling thread from the private pool it was bound to with
+    /// [`door_bind`].
+    ///
+    /// See [`DOOR_BIND(3C)`] for more details.
+    ///
+    /// [`DOOR_BIND(3C)`]: https://illumos.org/man/3c/door_bind
+    pub fn door_unbind() -> libc::c_int;
+
+    /// Look up one of a door's parameters, such as the largest request it will
+    /// accept. `param` is one of the `DOOR_PARAM_*` constants.
+    ///
+    /// See [`DOOR_GETPARAM(3C)`] for more details.
+    ///
+    /// [`DOOR_GETPARAM(3C)`]: https://illumos.org/man/3c/door_getparam
+    pub fn door_getparam(
+        d: libc::c_int,
+        param: libc::c_int,
+        out: *mut libc::size_t,
+    ) -> libc::c_int;
+
+    /// Fetch the credentials of the client whose call is being served.
+    ///
+    /// `*info` must either be null, in which case the credentials are
+    /// allocated afresh, or point to credentials from an earlier call, which
+    /// are reused. Either way they must eventually be released with
+    /// [`ucred_free`][super::ucred_h::ucred_free]. This only works from within
+    /// a server procedure.
+    ///
+    /// See [`DOOR_UCRED(3C)`] for more details.
+    ///
+    /// [`DOOR_UCRED(3C)`]: https://illumos.org/man/3c/door_ucred
+    pub fn door_ucred(info: *mut *mut super::ucred_h::ucred_t) -> libc::c_int;
+
+    /// Set one of a door's parameters. Only the door's server may do this.
+    ///
+    /// See [`DOOR_GETPARAM(3C)`] for more details.
+    ///
+    /// [`DOOR_GETPARAM(3C)`]: https://illumos.org/man/3c/door_getparam
+    pub fn door_setparam(
+        d: libc::c_int,
+        param: libc::c_int,
+        val: libc::size_t,
+    ) -> libc::c_int;
 }
 
 /// Arguments for, and Return Values from, a Door invocation.
@@ -241,6 +343,16 @@ pub const DOOR_RELEASE: door_attr_t = 0x40000; // Passed references are also rel
 /// Deliver an unref notification with door
 pub const DOOR_UNREF: door_attr_t = 0x01;
 
+/// The `argp` a server procedure receives in place of data when it is being
+/// notified that its door is unreferenced.
+///
+/// Defined in [door.h][1] as `((char *)1)`. The notification carries no data
+/// and no descriptors.
+///
+/// [1]: https://github.com/illumos/illumos-gate/blob/master/usr/src/uts/common/sys/door.h
+#[allow(clippy::manual_dangling_ptr)]
+pub const DOOR_UNREF_DATA: *const libc::c_char = 1 as *const libc::c_char;
+
 /// Use a private pool of server threads
 pub const DOOR_PRIVATE: door_attr_t = 0x02;
 
@@ -271,6 +383,19 @@ pub const DOOR_PRIVCREATE: door_attr_t = 0x200;
 /// Door has a private thread creation func
 pub const DOOR_DEPLETION_CB: door_attr_t = 0x400;
 
+/// Pass to `door_info` in place of a descriptor to ask about the door whose
+/// invocation this thread is currently serving.
+pub const DOOR_QUERY: libc::c_int = -2;
+
+/// Most descriptors a client may pass in one call
+pub const DOOR_PARAM_DESC_MAX: libc::c_int = 1;
+
+/// Most bytes of data a client may pass in one call
+pub const DOOR_PARAM_DATA_MAX: libc::c_int = 2;
+
+/// Fewest bytes of data a client may pass in one call
+pub const DOOR_PARAM_DATA_MIN: libc::c_int = 3;
+
 /// `d_data` component of [`door_desc_t`]
 ///
 /// This is not a real doors data structure *per se*, but rather the `d_data`
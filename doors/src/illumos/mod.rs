@@ -13,73 +13,217 @@
 
 pub mod door_h;
 pub mod errno_h;
+pub mod pthread_h;
 pub mod stropts_h;
-
+pub mod ucred_h;
+
+use std::fmt;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::io;
+use std::ops::BitAnd;
+use std::ops::BitAndAssign;
 use std::ops::BitOr;
 use std::ops::BitOrAssign;
+use std::ops::Sub;
+use std::ops::SubAssign;
 use std::os::fd::AsRawFd;
+use std::os::fd::BorrowedFd;
+use std::os::fd::FromRawFd;
+use std::os::fd::IntoRawFd;
+use std::os::fd::OwnedFd;
 use std::os::fd::RawFd;
 use std::os::unix::ffi::OsStrExt;
 use std::path::Path;
+use std::ptr;
+use std::sync::Arc;
+use std::sync::Mutex;
 
 /// Arguments passed to Door Call
 ///
 /// Consists of data and file descriptors. May also have a buffer for the return
-/// dta.
-pub struct DoorArg(door_h::door_arg_t);
+/// dta. That buffer is usually borrowed from the caller, but may instead be one
+/// we allocated ourselves in [`DoorArg::grow_rbuf`].
+pub struct DoorArg {
+    arg: door_h::door_arg_t,
+
+    /// The response buffer, when it is ours rather than the caller's.
+    owned_rbuf: Option<Box<[u8]>>,
+
+    /// Whether a door call has answered, and so whether the descriptors are
+    /// ones the kernel gave us rather than ones the caller lent.
+    answered: bool,
+}
 
 impl<'data, 'descriptors, 'response> DoorArg {
+    /// Describe a door call's buffers, as [`DoorArg::try_new`] does.
+    ///
+    /// # Panics
+    ///
+    /// If there are more descriptors than a `door_arg_t` can count. Use
+    /// [`DoorArg::try_new`] to get an error instead.
     pub fn new(
         data: &'data [u8],
         descriptors: &'descriptors [DoorFd],
         response: &'response mut [u8],
     ) -> Self {
+        match Self::try_new(data, descriptors, response) {
+            Ok(arg) => arg,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    /// Describe a door call's buffers, refusing any the kernel cannot be told
+    /// about.
+    ///
+    /// A `door_arg_t` counts descriptors with a `c_uint`, which is narrower
+    /// than `usize` on 64-bit systems. A length which does not fit is
+    /// refused with [`io::ErrorKind::InvalidInput`], rather than truncated:
+    /// the kernel would otherwise be told about fewer descriptors than the
+    /// caller meant to send. The data and response lengths are `size_t`s,
+    /// which are as wide as `usize`, so they always fit.
+    pub fn try_new(
+        data: &'data [u8],
+        descriptors: &'descriptors [DoorFd],
+        response: &'response mut [u8],
+    ) -> io::Result<Self> {
         let data_ptr = data.as_ptr() as *const libc::c_char;
-        let data_size = data.len() as libc::size_t;
+        let data_size: libc::size_t = data.len();
         let desc_ptr = descriptors.as_ptr() as *const door_h::door_desc_t;
-        let desc_num = descriptors.len() as libc::c_uint;
+        let desc_num = desc_num(descriptors.len())?;
         let rbuf = response.as_ptr() as *const libc::c_char;
-        let rsize = response.len() as libc::size_t;
-        Self(door_h::door_arg_t {
-            data_ptr,
-            data_size,
-            desc_ptr,
-            desc_num,
-            rbuf,
-            rsize,
+        let rsize: libc::size_t = response.len();
+        Ok(Self {
+            arg: door_h::door_arg_t {
+                data_ptr,
+                data_size,
+                desc_ptr,
+                desc_num,
+                rbuf,
+                rsize,
+            },
+            owned_rbuf: None,
+            answered: false,
         })
     }
 
     pub fn data(&'data self) -> &'data [u8] {
         unsafe {
             std::slice::from_raw_parts(
-                self.0.data_ptr as *const u8,
-                self.0.data_size,
+                self.arg.data_ptr as *const u8,
+                self.arg.data_size,
+            )
+        }
+    }
+
+    pub fn descriptors(&'descriptors self) -> &'descriptors [DoorFd] {
+        unsafe {
+            std::slice::from_raw_parts(
+                self.arg.desc_ptr as *const DoorFd,
+                self.arg.desc_num as usize,
             )
         }
     }
 
     pub fn rbuf(&'response self) -> &'response [u8] {
         unsafe {
-            std::slice::from_raw_parts(self.0.rbuf as *const u8, self.0.rsize)
+            std::slice::from_raw_parts(
+                self.arg.rbuf as *const u8,
+                self.arg.rsize,
+            )
         }
     }
 
+    /// Whether a door call has written its results into this argument.
+    pub fn is_answered(&self) -> bool {
+        self.answered
+    }
+
+    /// Note that a door call has written its results into this argument.
+    pub(crate) fn mark_answered(&mut self) {
+        self.answered = true;
+    }
+
     pub fn rbuf_addr(&self) -> u64 {
-        self.0.rbuf as u64
+        self.arg.rbuf as u64
     }
 
     pub fn as_door_arg_t(&self) -> &'_ door_h::door_arg_t {
-        &(self.0)
+        &self.arg
     }
 
     pub fn as_mut_door_arg_t(&mut self) -> &'_ mut door_h::door_arg_t {
-        &mut (self.0)
+        &mut self.arg
+    }
+
+    /// Replace the response buffer with one of our own, at least `n` bytes long.
+    ///
+    /// The current contents of the buffer are carried over, and if the data
+    /// pointer refers to somewhere inside the old buffer, it is moved to the
+    /// same place in the new one. Does nothing if the buffer is already big
+    /// enough. This must not be used once the kernel has mapped a buffer for
+    /// us, since that mapping would then be forgotten rather than unmapped.
+    pub fn grow_rbuf(&mut self, n: usize) {
+        if self.arg.rsize >= n {
+            return;
+        }
+        let mut buffer = vec![0u8; n].into_boxed_slice();
+        let old = self.rbuf();
+        buffer[..old.len()].copy_from_slice(old);
+
+        let old_range = old.as_ptr_range();
+        let data_ptr = self.arg.data_ptr as *const u8;
+        if old_range.contains(&data_ptr) {
+            let offset = data_ptr as usize - old_range.start as usize;
+            self.arg.data_ptr =
+                buffer[offset..].as_ptr() as *const libc::c_char;
+        }
+        self.arg.rbuf = buffer.as_ptr() as *const libc::c_char;
+        self.arg.rsize = n;
+        self.owned_rbuf = Some(buffer);
+    }
+
+    /// Copy the response out of the response buffer into one of our own,
+    /// just long enough to hold it, so that the original buffer can be reused.
+    ///
+    /// Everything up to the end of the response data or of the descriptors,
+    /// whichever comes last, is carried over at the same offset, and the data
+    /// and descriptor pointers are moved along with it. Like
+    /// [`DoorArg::grow_rbuf`], this must not be used on a buffer the kernel has
+    /// mapped for us.
+    pub fn own_response(&mut self) {
+        let old = self.rbuf().as_ptr_range();
+        let start = old.start as usize;
+        let within = |ptr: usize| old.contains(&(ptr as *const u8));
+        let data_ptr = self.arg.data_ptr as usize;
+        let desc_ptr = self.arg.desc_ptr as usize;
+        let desc_size = self.arg.desc_num as usize
+            * std::mem::size_of::<door_h::door_desc_t>();
+        let mut len = 0;
+        if within(data_ptr) {
+            len = len.max(data_ptr - start + self.arg.data_size);
+        }
+        if within(desc_ptr) {
+            len = len.max(desc_ptr - start + desc_size);
+        }
+
+        let buffer: Box<[u8]> = self.rbuf()[..len].into();
+        let new = buffer.as_ptr() as usize;
+        if within(data_ptr) {
+            self.arg.data_ptr = (new + data_ptr - start) as *const libc::c_char;
+        }
+        if within(desc_ptr) {
+            self.arg.desc_ptr =
+                (new + desc_ptr - start) as *const door_h::door_desc_t;
+        }
+        self.arg.rbuf = buffer.as_ptr() as *const libc::c_char;
+        self.arg.rsize = len;
+        self.owned_rbuf = Some(buffer);
     }
 
     pub fn munmap_rbuf(&mut self) -> Result<(), MunmapError> {
         match unsafe {
-            libc::munmap(self.0.rbuf as *mut libc::c_void, self.0.rsize)
+            libc::munmap(self.arg.rbuf as *mut libc::c_void, self.arg.rsize)
         } {
             0 => Ok(()),
             _ => match errno_h::errno() {
@@ -98,6 +242,12 @@ pub enum MunmapError {
 pub struct DoorFd(door_h::door_desc_t);
 
 impl AsRawFd for DoorFd {
+    /// The descriptor number this `DoorFd` carries.
+    ///
+    /// This is the one place that reads the `d_data` union. Every `DoorFd` is
+    /// made by [`DoorFd::new`] (or one of the constructors built on it), which
+    /// always fills in `d_desc` and marks it [`door_h::DOOR_DESCRIPTOR`], so
+    /// `d_desc` is always the initialized member.
     fn as_raw_fd(&self) -> RawFd {
         let d_data = &self.0.d_data;
         let d_desc = unsafe { d_data.d_desc };
@@ -106,6 +256,29 @@ impl AsRawFd for DoorFd {
     }
 }
 
+/// Two `DoorFd`s are equal if they carry the same descriptor number with the
+/// same handling instructions, so a server can keep them in a `HashSet`, for
+/// instance to track the descriptors its clients have subscribed with.
+///
+/// This compares descriptor *numbers*, not the files behind them: two
+/// duplicates of one file are different, and a number which has been closed
+/// and reused is the same.
+impl PartialEq for DoorFd {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_raw_fd() == other.as_raw_fd()
+            && self.0.d_attributes == other.0.d_attributes
+    }
+}
+
+impl Eq for DoorFd {}
+
+impl Hash for DoorFd {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_raw_fd().hash(state);
+        self.0.d_attributes.hash(state);
+    }
+}
+
 impl DoorFd {
     /// Create a new `door_desc_t` from a file descriptor.
     ///
@@ -140,9 +313,42 @@ impl DoorFd {
         })
     }
 
+    /// Send a descriptor we own, handing it over to the recipient.
+    ///
+    /// Ownership passes to the kernel, which closes our copy once the call (or
+    /// return) it is sent with has completed, so this always sets
+    /// `DOOR_RELEASE`. A `DoorFd` does not close anything itself: if this one
+    /// is never sent, the descriptor leaks.
+    pub fn from_owned(fd: OwnedFd) -> Self {
+        Self::new(fd.into_raw_fd(), true)
+    }
+
+    /// Send a copy of a descriptor we are only borrowing.
+    ///
+    /// The recipient gets its own duplicate, and ours stays open, so this never
+    /// sets `DOOR_RELEASE`. A `DoorFd` cannot hold on to the borrow, so it is up
+    /// to the caller to keep the descriptor open until the `DoorFd` has been
+    /// sent.
+    pub fn from_borrowed(fd: BorrowedFd<'_>) -> Self {
+        Self::new(fd.as_raw_fd(), false)
+    }
+
     pub fn will_release(&self) -> bool {
         self.0.d_attributes == (door_h::DOOR_DESCRIPTOR | door_h::DOOR_RELEASE)
     }
+
+    /// Treat this descriptor as a client of the door it refers to.
+    ///
+    /// This is how a client makes use of a door that a server sent back in
+    /// response to a call, and it takes ownership of the descriptor. The
+    /// descriptor is checked with [`door_info`] first. If it does not refer to
+    /// a door, it is handed back as an [`OwnedFd`] along with an
+    /// [`io::ErrorKind::InvalidInput`] error, just as
+    /// [`Client::from_owned_fd`][crate::Client::from_owned_fd] does.
+    pub fn into_client(self) -> Result<crate::Client, (OwnedFd, io::Error)> {
+        let fd = unsafe { OwnedFd::from_raw_fd(self.as_raw_fd()) };
+        crate::Client::from_owned_fd(fd)
+    }
 }
 
 /// illumos Error Conditions
@@ -167,6 +373,12 @@ pub enum Error {
     /// * `fattach` - The path argument is a file in a remotely mounted directory.
     ///   Alternatively, the fildes argument does not represent a doors file.
     /// * `door_create` - invalid attributes were passed
+    /// * `door_bind` - the door was not created with `DOOR_PRIVATE`, or the
+    ///   calling thread is already bound to a door
+    /// * `fdetach` - the path is not attached to anything
+    ///
+    /// A bound thread's `door_return` also fails with `EINVAL` when its door
+    /// is gone, but [`door_wait`] reports that as [`Error::DoorGone`].
     EINVAL,
 
     /// Too many symbolic links were encountered in translating path.
@@ -191,6 +403,37 @@ pub enum Error {
 
     /// Bad address
     EFAULT,
+
+    /// Resources were temporarily unavailable. Try again.
+    EAGAIN,
+
+    /// There was not enough memory.
+    ENOMEM,
+
+    /// The door this thread is bound to no longer exists.
+    ///
+    /// A thread which has been bound to a door with [`door_bind`] can only
+    /// serve that door. Once the door is revoked, `door_return` sends the
+    /// thread back with `EINVAL`, which would otherwise also mean that the
+    /// results passed to it were bad. [`door_wait`] has no results to pass, so
+    /// it reports this variant instead. A bound thread which sees it has
+    /// nothing left to do, and should exit or bind itself to another door.
+    DoorGone,
+
+    /// The path contains a NUL byte, so it cannot be handed to the kernel.
+    NulInPath,
+
+    /// The kernel returned an errno the man page does not document.
+    Unknown(libc::c_int),
+}
+
+/// Spell a path the way the kernel wants it.
+///
+/// Paths are taken byte for byte, so those which are not valid UTF-8 are fine.
+/// Only a NUL byte, which would cut the path short, is refused.
+fn c_path<P: AsRef<Path>>(path: P) -> Result<std::ffi::CString, Error> {
+    let path_bytes = path.as_ref().as_os_str().as_bytes();
+    std::ffi::CString::new(path_bytes).map_err(|_| Error::NulInPath)
 }
 
 /// Attach a doors-based file descriptor to an object in the file system name
@@ -200,9 +443,7 @@ pub enum Error {
 ///
 /// [`FATTACH(3C)`]: https://illumos.org/man/3C/fattach
 pub fn fattach<P: AsRef<Path>>(fildes: RawFd, path: P) -> Result<(), Error> {
-    let path_bytes = path.as_ref().as_os_str().as_bytes();
-    // TODO: Why is it safe to unwrap here?
-    let c_string = std::ffi::CString::new(path_bytes).unwrap();
+    let c_string = c_path(path)?;
     match unsafe { stropts_h::fattach(fildes, c_string.as_ptr()) } {
         0 => Ok(()),
         _ => match errno_h::errno() {
@@ -220,6 +461,27 @@ pub fn fattach<P: AsRef<Path>>(fildes: RawFd, path: P) -> Result<(), Error> {
     }
 }
 
+/// Withdraw a door from the file system name space.
+///
+/// See [`FDETACH(3C)`] for more details.
+///
+/// [`FDETACH(3C)`]: https://illumos.org/man/3C/fdetach
+pub fn fdetach<P: AsRef<Path>>(path: P) -> Result<(), Error> {
+    let c_string = c_path(path)?;
+    match unsafe { stropts_h::fdetach(c_string.as_ptr()) } {
+        0 => Ok(()),
+        _ => match errno_h::errno() {
+            libc::EINVAL => Err(Error::EINVAL),
+            libc::ELOOP => Err(Error::ELOOP),
+            libc::ENAMETOOLONG => Err(Error::ENAMETOOLONG),
+            libc::ENOENT => Err(Error::ENOENT),
+            libc::ENOTDIR => Err(Error::ENOTDIR),
+            libc::EPERM => Err(Error::EPERM),
+            _ => unreachable!(),
+        },
+    }
+}
+
 /// Raw, Unvarnished Server Procedure
 ///
 /// This is a function that literally matches the signature given in
@@ -230,7 +492,23 @@ pub fn fattach<P: AsRef<Path>>(fildes: RawFd, path: P) -> Result<(), Error> {
 pub type ServerProcedure = door_h::door_server_procedure_t;
 
 /// Flags that represent a door's behavior
-#[derive(Debug, PartialEq)]
+///
+/// This is a set of flags, in the style of the `bitflags` crate. Each named
+/// constructor gives the set holding just that flag; combine them with `|`,
+/// take them apart with `&` and `-`, and query them with
+/// [`DoorAttributes::contains`] and [`DoorAttributes::intersects`]. The
+/// [`Default`] is the empty set, [`DoorAttributes::none`].
+///
+/// ```
+/// use doors::illumos::DoorAttributes;
+///
+/// let mut attrs = DoorAttributes::default();
+/// attrs.insert(DoorAttributes::unref() | DoorAttributes::refuse_desc());
+/// attrs.remove(DoorAttributes::refuse_desc());
+/// assert_eq!(attrs, DoorAttributes::unref());
+/// assert!(!attrs.intersects(DoorAttributes::private()));
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub struct DoorAttributes {
     attrs: u32,
 }
@@ -243,12 +521,18 @@ impl DoorAttributes {
 
     /// Ask for the server procedure to receive a special shutdown invocation
     /// when it is no longer being referenced by active clients.
+    ///
+    /// This notification is delivered at most once. See
+    /// `Request::is_unref` and `Serve::unreferenced` for how to recognize it.
     pub fn unref() -> Self {
         Self {
             attrs: door_h::DOOR_UNREF,
         }
     }
 
+    /// Like [`DoorAttributes::unref`], but the notification is delivered every
+    /// time the door goes from having clients to having none, rather than
+    /// only the first time.
     pub fn unref_multi() -> Self {
         Self {
             attrs: door_h::DOOR_UNREF_MULTI,
@@ -312,6 +596,36 @@ impl DoorAttributes {
     pub fn get(&self) -> u32 {
         self.attrs
     }
+
+    /// Whether no flags are set.
+    pub fn is_empty(&self) -> bool {
+        self.attrs == 0
+    }
+
+    /// Whether every flag in `other` is also set here.
+    pub fn contains(&self, other: Self) -> bool {
+        self.attrs & other.attrs == other.attrs
+    }
+
+    /// Whether any flag in `other` is also set here.
+    pub fn intersects(&self, other: Self) -> bool {
+        self.attrs & other.attrs != 0
+    }
+
+    /// Set the flags in `other`.
+    pub fn insert(&mut self, other: Self) {
+        self.attrs |= other.attrs;
+    }
+
+    /// Clear the flags in `other`.
+    pub fn remove(&mut self, other: Self) {
+        self.attrs &= !other.attrs;
+    }
+
+    /// Flip the flags in `other`.
+    pub fn toggle(&mut self, other: Self) {
+        self.attrs ^= other.attrs;
+    }
 }
 
 impl BitOr for DoorAttributes {
@@ -330,8 +644,46 @@ impl BitOrAssign for DoorAttributes {
     }
 }
 
+impl BitAnd for DoorAttributes {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        Self {
+            attrs: self.attrs & rhs.attrs,
+        }
+    }
+}
+
+impl BitAndAssign for DoorAttributes {
+    fn bitand_assign(&mut self, rhs: Self) {
+        self.attrs &= rhs.attrs;
+    }
+}
+
+impl Sub for DoorAttributes {
+    type Output = Self;
+
+    /// The flags set here but not in `rhs`.
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self {
+            attrs: self.attrs & !rhs.attrs,
+        }
+    }
+}
+
+impl SubAssign for DoorAttributes {
+    fn sub_assign(&mut self, rhs: Self) {
+        self.remove(rhs);
+    }
+}
+
 /// Create a door descriptor from a server procedure and a cookie.
 ///
+/// The procedure must be a genuine [`ServerProcedure`], which is all the safe
+/// API ever passes here. In debug builds, the new door is checked with
+/// [`door_info`] to make sure it belongs to this process and to the procedure
+/// given. [`door_h::door_create`] is the unchecked escape hatch.
+///
 /// See [`DOOR_CREATE(3C)`] for more details.
 ///
 /// [`DOOR_CREATE(3C)`]: https://illumos.org/man/3C/door_create
@@ -353,16 +705,265 @@ pub fn door_create(
             libc::EMFILE => Err(Error::EMFILE),
             _ => unreachable!(),
         },
+        fd => {
+            // Nothing stops the raw door_create from being handed a bogus
+            // procedure, so in debug builds make sure we really did get a
+            // door to that procedure, in this process.
+            if cfg!(debug_assertions) {
+                let info = door_info(fd as RawFd)
+                    .expect("door_create returned something other than a door");
+                debug_assert_eq!(info.target(), std::process::id());
+                debug_assert_eq!(
+                    info.proc() as usize,
+                    server_procedure as usize
+                );
+            }
+            Ok(fd as RawFd)
+        }
+    }
+}
+
+/// Create a door, like [`door_create`], but own the resulting descriptor.
+///
+/// Dropping the [`OwnedFd`] *closes* the descriptor; it does not revoke the
+/// door. Clients which already hold a descriptor for the door, or which open
+/// it through a path it was [`fattach`]ed to, can go on calling it. Revoke it
+/// with [`door_h::door_revoke`] first if that is not what you want, or use
+/// [`Door`][crate::server::Door], which revokes its door when dropped.
+pub fn door_create_owned(
+    server_procedure: ServerProcedure,
+    cookie: u64,
+    attributes: DoorAttributes,
+) -> Result<OwnedFd, Error> {
+    let fd = door_create(server_procedure, cookie, attributes)?;
+    Ok(unsafe { OwnedFd::from_raw_fd(fd) })
+}
+
+/// How [`door_xcreate`] makes the threads of a door's private pool
+#[derive(Default, Clone)]
+pub struct DoorThreads {
+    /// The size of each thread's stack, or Rust's default if `None`.
+    pub stack_size: Option<usize>,
+
+    /// What to name each thread, for the benefit of tools like `pstack` and
+    /// `mdb`. illumos allows at most [`pthread_h::PTHREAD_MAX_NAMELEN_NP`]
+    /// bytes, terminating NUL included, so longer names are cut short; a name
+    /// containing a NUL is not used at all.
+    pub name: Option<String>,
+
+    /// Asked whether to add a thread each time the pool runs dry, that is,
+    /// whenever a call arrives and every thread is busy. Returning `false`
+    /// declines, and the call waits for a thread to come free. Without this,
+    /// a thread is always added. Not consulted for the initial threads, nor
+    /// at all for doors with [`DoorAttributes::no_depletion_callback`].
+    pub on_depletion: Option<Arc<dyn Fn() -> bool + Send + Sync>>,
+}
+
+impl fmt::Debug for DoorThreads {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DoorThreads")
+            .field("stack_size", &self.stack_size)
+            .field("name", &self.name)
+            .field("on_depletion", &self.on_depletion.is_some())
+            .finish()
+    }
+}
+
+impl DoorThreads {
+    /// The name to give the OS thread, shortened to fit.
+    fn os_name(&self) -> Option<std::ffi::CString> {
+        let name = self.name.as_deref()?;
+        let mut end = name.len().min(pthread_h::PTHREAD_MAX_NAMELEN_NP - 1);
+        while !name.is_char_boundary(end) {
+            end -= 1;
+        }
+        std::ffi::CString::new(&name[..end]).ok()
+    }
+}
+
+/// Where [`door_xcreate`] keeps the [`DoorThreads`] for a door
+///
+/// illumos may ask for another thread at any time while the door lives, so the
+/// slot itself has to live forever. What it holds need not: once the door has
+/// been revoked, [`DoorThreadsSlot::clear`] drops the recipe, and any thread
+/// asked for after that is declined. A thread being spawned at that moment
+/// holds its own reference to the recipe until it has started.
+pub struct DoorThreadsSlot(Mutex<Option<Arc<DoorThreads>>>);
+
+impl DoorThreadsSlot {
+    pub fn new(threads: DoorThreads) -> Self {
+        Self(Mutex::new(Some(Arc::new(threads))))
+    }
+
+    /// Drop the recipe, so that no more threads are made from it.
+    pub fn clear(&self) {
+        // Drop the recipe outside of the lock, as its closure may be anything.
+        let threads = self.0.lock().unwrap().take();
+        drop(threads);
+    }
+}
+
+/// Create a door whose server threads are made to measure.
+///
+/// The door gets a private pool, started with `nthread` threads (at least
+/// one), which illumos tops up whenever it runs dry unless `attributes`
+/// include [`DoorAttributes::no_depletion_callback`]. Every thread is a Rust
+/// thread spawned as the recipe in `threads` describes, which is why the slot
+/// must live forever: illumos may ask for another thread at any time. Clear it
+/// once the door is revoked, or if this fails. [`DoorAttributes::private`] is
+/// implied, and left out of what is passed to the kernel.
+///
+/// See [`DOOR_XCREATE(3C)`] for more details.
+///
+/// [`DOOR_XCREATE(3C)`]: https://illumos.org/man/3C/door_xcreate
+pub fn door_xcreate(
+    server_procedure: ServerProcedure,
+    cookie: u64,
+    attributes: DoorAttributes,
+    threads: &'static DoorThreadsSlot,
+    nthread: usize,
+) -> Result<RawFd, Error> {
+    let attributes = attributes - DoorAttributes::private();
+    let nthread = nthread.clamp(1, libc::c_int::MAX as usize);
+    let result = unsafe {
+        door_h::door_xcreate(
+            server_procedure,
+            cookie as *const libc::c_void,
+            attributes.get(),
+            spawn_door_thread,
+            None,
+            threads as *const DoorThreadsSlot as *mut libc::c_void,
+            nthread as libc::c_int,
+        )
+    };
+    match result {
+        -1 => match errno_h::errno() {
+            libc::EINVAL => Err(Error::EINVAL),
+            libc::EMFILE => Err(Error::EMFILE),
+            libc::ENOMEM => Err(Error::ENOMEM),
+            // The initial threads could not be created.
+            _ => Err(Error::EAGAIN),
+        },
         fd => Ok(fd as RawFd),
     }
 }
 
+/// The thread factory behind [`door_xcreate`]: `crcookie` is the
+/// [`DoorThreadsSlot`] it was given.
+///
+/// illumos passes no `info` while creating the initial threads, and the door's
+/// info once the pool has run dry. No thread is made once the slot is clear.
+extern "C" fn spawn_door_thread(
+    info: *mut door_h::door_info_t,
+    start: extern "C" fn(*mut libc::c_void) -> *mut libc::c_void,
+    arg: *mut libc::c_void,
+    crcookie: *mut libc::c_void,
+) -> libc::c_int {
+    let slot = unsafe { &*(crcookie as *const DoorThreadsSlot) };
+    let threads = match slot.0.lock().unwrap().clone() {
+        Some(threads) => threads,
+        None => return 0,
+    };
+    if let Some(on_depletion) = &threads.on_depletion {
+        if !info.is_null() && !on_depletion() {
+            return 0;
+        }
+    }
+    let mut builder = std::thread::Builder::new();
+    if let Some(stack_size) = threads.stack_size {
+        builder = builder.stack_size(stack_size);
+    }
+    if let Some(name) = &threads.name {
+        // This names the thread for Rust, as in panic messages.
+        builder = builder.name(name.clone());
+    }
+    let os_name = threads.os_name();
+    // Raw pointers are not Send, so carry the argument across as an address.
+    let arg = arg as usize;
+    let spawned = builder.spawn(move || {
+        if let Some(name) = os_name {
+            // And this names it for the system, as in pstack.
+            let this = unsafe { libc::pthread_self() };
+            unsafe { pthread_h::pthread_setname_np(this, name.as_ptr()) };
+        }
+        start(arg as *mut libc::c_void);
+    });
+    match spawned {
+        Ok(_) => 1,
+        Err(_) => -1,
+    }
+}
+
+/// Bind the calling thread to the private server pool of a door.
+///
+/// See [`DOOR_BIND(3C)`] for more details.
+///
+/// [`DOOR_BIND(3C)`]: https://illumos.org/man/3C/door_bind
+pub fn door_bind(fd: RawFd) -> Result<(), Error> {
+    match unsafe { door_h::door_bind(fd) } {
+        0 => Ok(()),
+        _ => match errno_h::errno() {
+            libc::EBADF => Err(Error::EBADF),
+            libc::EINVAL => Err(Error::EINVAL),
+            _ => unreachable!(),
+        },
+    }
+}
+
+/// Offer the calling thread up to answer door invocations.
+///
+/// This is `door_return` with no results, as a freshly bound thread would call
+/// it. It only comes back if the thread cannot wait, which for a thread bound
+/// with [`door_bind`] usually means [`Error::DoorGone`].
+///
+/// See [`DOOR_RETURN(3C)`] for more details.
+///
+/// [`DOOR_RETURN(3C)`]: https://illumos.org/man/3C/door_return
+pub fn door_wait() -> Error {
+    unsafe { door_h::door_return(ptr::null(), 0, ptr::null(), 0) };
+    match errno_h::errno() {
+        // With nothing to return, our arguments can't be what's invalid.
+        libc::EINVAL => Error::DoorGone,
+        libc::EFAULT => Error::EFAULT,
+        libc::EMFILE => Error::EMFILE,
+        errno => Error::Unknown(errno),
+    }
+}
+
+/// Set or clear the close-on-exec flag on a descriptor.
+///
+/// Door descriptors which survive an [`EXEC(2)`] can leak into child processes
+/// that have no business calling (or serving) them, so the safe layer sets
+/// `FD_CLOEXEC` on every descriptor it creates. This is the same default that
+/// [`std::fs::File`] uses.
+///
+/// [`EXEC(2)`]: https://illumos.org/man/2/exec
+pub(crate) fn set_cloexec(fd: RawFd, cloexec: bool) -> io::Result<()> {
+    let flags = match unsafe { libc::fcntl(fd, libc::F_GETFD) } {
+        -1 => return Err(io::Error::last_os_error()),
+        flags => flags,
+    };
+    let flags = match cloexec {
+        true => flags | libc::FD_CLOEXEC,
+        false => flags & !libc::FD_CLOEXEC,
+    };
+    match unsafe { libc::fcntl(fd, libc::F_SETFD, flags) } {
+        -1 => Err(io::Error::last_os_error()),
+        _ => Ok(()),
+    }
+}
+
 /// Door Metadata
 ///
 /// Contains information about the door server, such as its pid, memory location
 /// of the server procedure, the cookie value, and any attributes. Any client
 /// able to obtain a valid file descriptor for a door can read this information.
-#[derive(Default, Clone, Copy, Debug, PartialEq)]
+///
+/// The reserved bytes at the end of [`door_info_t`][door_h::door_info_t] play no
+/// part in comparing or printing a `DoorInfo`, so that a future illumos which
+/// puts something there does not make otherwise identical doors look different.
+/// They remain available through [`DoorInfo::raw_reserved`].
+#[derive(Default, Clone, Copy)]
 pub struct DoorInfo(door_h::door_info_t);
 
 /// Lookup metadata for a door
@@ -399,6 +1000,198 @@ impl DoorInfo {
     pub fn id(&self) -> u64 {
         self.0.di_uniquifier
     }
+
+    /// Whether the door is served by this process.
+    pub fn belongs_to_current_process(&self) -> bool {
+        self.target() == std::process::id()
+    }
+
+    /// Whether the door's server has revoked it.
+    pub fn is_revoked(&self) -> bool {
+        self.attributes().get() & door_h::DOOR_REVOKED != 0
+    }
+
+    /// Whether the door is served by a private pool of threads.
+    pub fn is_private(&self) -> bool {
+        self.attributes().get() & door_h::DOOR_PRIVATE != 0
+    }
+
+    /// Whether the door has no clients at the moment.
+    pub fn is_unreferenced(&self) -> bool {
+        self.attributes().get() & door_h::DOOR_IS_UNREF != 0
+    }
+
+    /// Panic unless the door is served by process `pid` and has the given
+    /// `cookie`, saying which of the two is wrong.
+    ///
+    /// This is for tests which need to be sure they are talking to the door
+    /// they think they are, and is only available with the `testing` feature.
+    #[cfg(feature = "testing")]
+    pub fn assert_matches(&self, pid: u32, cookie: u64) {
+        assert_eq!(self.target(), pid, "door is served by the wrong process");
+        assert_eq!(self.cookie(), cookie, "door has the wrong cookie");
+    }
+
+    /// The reserved words of [`door_info_t`][door_h::door_info_t], verbatim.
+    ///
+    /// These are zero on every illumos release to date, and have no documented
+    /// meaning.
+    pub fn raw_reserved(&self) -> [libc::c_int; 4] {
+        self.0.di_resv
+    }
+}
+
+impl PartialEq for DoorInfo {
+    fn eq(&self, other: &Self) -> bool {
+        self.target() == other.target()
+            && self.proc() == other.proc()
+            && self.cookie() == other.cookie()
+            && self.attributes() == other.attributes()
+            && self.id() == other.id()
+    }
+}
+
+impl std::fmt::Debug for DoorInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DoorInfo")
+            .field("target", &self.target())
+            .field("proc", &self.proc())
+            .field("cookie", &self.cookie())
+            .field("attributes", &self.attributes())
+            .field("id", &self.id())
+            .finish()
+    }
+}
+
+/// Count `len` descriptors the way a `door_arg_t` does, if it can.
+fn desc_num(len: usize) -> io::Result<libc::c_uint> {
+    libc::c_uint::try_from(len).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} descriptors are too many for one door call", len),
+        )
+    })
+}
+
+/// Credentials of the client whose call is being served
+///
+/// These come from [`door_ucred`], and describe the client as it was when it
+/// made the call. Each field can, in principle, be unavailable, in which case
+/// its accessor returns `None`.
+pub struct Ucred(*mut ucred_h::ucred_t);
+
+impl Ucred {
+    /// The client's effective user id.
+    pub fn euid(&self) -> Option<libc::uid_t> {
+        match unsafe { ucred_h::ucred_geteuid(self.0) } {
+            libc::uid_t::MAX => None,
+            euid => Some(euid),
+        }
+    }
+
+    /// The client's effective group id.
+    pub fn egid(&self) -> Option<libc::gid_t> {
+        match unsafe { ucred_h::ucred_getegid(self.0) } {
+            libc::gid_t::MAX => None,
+            egid => Some(egid),
+        }
+    }
+
+    /// The client's process id.
+    pub fn pid(&self) -> Option<libc::pid_t> {
+        match unsafe { ucred_h::ucred_getpid(self.0) } {
+            -1 => None,
+            pid => Some(pid),
+        }
+    }
+
+    /// The zone the client is running in.
+    ///
+    /// Clients in other zones can reach a door through a filesystem shared
+    /// with them, so a server which serves several zones should check this
+    /// before trusting the user id, which means something different in each.
+    pub fn zone_id(&self) -> Option<libc::c_int> {
+        match unsafe { ucred_h::ucred_getzoneid(self.0) } {
+            -1 => None,
+            zone_id => Some(zone_id),
+        }
+    }
+
+    /// The client's audit session id.
+    pub fn session_id(&self) -> Option<libc::pid_t> {
+        match unsafe { ucred_h::ucred_getasid(self.0) } {
+            -1 => None,
+            session_id => Some(session_id),
+        }
+    }
+
+    /// Everything these credentials say about which process is calling.
+    ///
+    /// Returns `None` if the pid is unavailable, since the rest means little
+    /// without it. See [`ClientIdentity`] for how far this can be trusted.
+    pub fn identity(&self) -> Option<ClientIdentity> {
+        Some(ClientIdentity {
+            pid: self.pid()?,
+            zone_id: self.zone_id(),
+            session_id: self.session_id(),
+        })
+    }
+}
+
+/// The process behind a door call, as best the credentials can tell
+///
+/// Servers which keep state per client want a key which names one process,
+/// not just whichever process has its pid now. A pid alone is a poor key:
+/// once the client exits, the pid can be handed to an unrelated process. This
+/// pairs it with the zone and audit session, so that a reused pid only
+/// matches if the new process is also in the same zone and the same login
+/// session.
+///
+/// That narrows things, but does not settle them. The credentials from
+/// [`door_ucred`] carry no process start time, nor anything else unique to
+/// one process, so two processes run one after the other from the same shell
+/// can still look alike. Servers which need more can read the start time
+/// from `/proc/<pid>/psinfo` while serving the call: the client is blocked in
+/// `door_call` until the server returns, so its pid cannot be reused in the
+/// meantime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ClientIdentity {
+    /// The client's process id.
+    pub pid: libc::pid_t,
+
+    /// The zone the client is running in, if known.
+    pub zone_id: Option<libc::c_int>,
+
+    /// The client's audit session id, if known.
+    pub session_id: Option<libc::pid_t>,
+}
+
+impl Drop for Ucred {
+    fn drop(&mut self) {
+        unsafe { ucred_h::ucred_free(self.0) }
+    }
+}
+
+/// Fetch the credentials of the client whose call is being served.
+///
+/// This only works on a door thread, while it is serving a call; anywhere else
+/// it fails with [`Error::EINVAL`].
+///
+/// See [`DOOR_UCRED(3C)`] for more details.
+///
+/// [`DOOR_UCRED(3C)`]: https://illumos.org/man/3C/door_ucred
+pub fn door_ucred() -> Result<Ucred, Error> {
+    let mut ucred = ptr::null_mut();
+    match unsafe { door_h::door_ucred(&mut ucred) } {
+        0 => Ok(Ucred(ucred)),
+        _ => match errno_h::errno() {
+            libc::EAGAIN => Err(Error::EAGAIN),
+            libc::EFAULT => Err(Error::EFAULT),
+            libc::EINVAL => Err(Error::EINVAL),
+            libc::ENOMEM => Err(Error::ENOMEM),
+            _ => unreachable!(),
+        },
+    }
 }
 
 #[cfg(test)]
@@ -406,6 +1199,7 @@ mod tests {
     use super::*;
     use libc;
     use std::ffi::CString;
+    use std::os::fd::AsFd;
 
     #[test]
     fn errno_works() {
@@ -416,6 +1210,33 @@ mod tests {
         assert_eq!(errno_h::errno(), libc::ENOENT);
     }
 
+    #[test]
+    fn nul_in_path_is_refused() {
+        let path = "/tmp/nul\0in_path.door";
+        assert_eq!(fattach(-1, path), Err(Error::NulInPath));
+        assert_eq!(fdetach(path), Err(Error::NulInPath));
+    }
+
+    #[test]
+    fn door_ucred_needs_a_call() {
+        assert!(matches!(door_ucred(), Err(Error::EINVAL)));
+    }
+
+    #[test]
+    fn owned_descriptors_are_released() {
+        let file = std::fs::File::open("/dev/null").unwrap();
+        let fd = file.as_raw_fd();
+
+        let borrowed = DoorFd::from_borrowed(file.as_fd());
+        assert!(!borrowed.will_release());
+        assert_eq!(borrowed.as_raw_fd(), fd);
+
+        let owned = DoorFd::from_owned(OwnedFd::from(file));
+        assert!(owned.will_release());
+        assert_eq!(owned.as_raw_fd(), fd);
+        unsafe { libc::close(fd) };
+    }
+
     #[test]
     fn door_info_error() {
         let e = door_info(-1);
@@ -436,6 +1257,7 @@ mod tests {
         let fd = door_create(hello, 0, DoorAttributes::none()).unwrap();
         let info = door_info(fd).unwrap();
         assert_eq!(info.target(), std::process::id());
+        assert!(info.belongs_to_current_process());
     }
 
     #[test]
@@ -542,6 +1364,120 @@ mod tests {
         assert_ne!(info1.proc(), info2.proc());
     }
 
+    #[test]
+    fn owned_doors_are_closed_on_drop() {
+        extern "C" fn hello(
+            _cookie: *const libc::c_void,
+            _argp: *const libc::c_char,
+            _arg_size: libc::size_t,
+            _dp: *const door_h::door_desc_t,
+            _n_desc: libc::c_uint,
+        ) {
+        }
+
+        let owned =
+            door_create_owned(hello, 0, DoorAttributes::none()).unwrap();
+        let fd = owned.as_raw_fd();
+        assert_eq!(door_info(fd).unwrap().cookie(), 0);
+
+        drop(owned);
+        assert_eq!(unsafe { libc::fcntl(fd, libc::F_GETFD) }, -1);
+    }
+
+    #[test]
+    fn door_thread_names_are_cut_short() {
+        let threads = DoorThreads {
+            name: Some("é".repeat(20)),
+            ..Default::default()
+        };
+        let name = threads.os_name().unwrap();
+        assert_eq!(name.as_bytes().len(), 30);
+
+        let threads = DoorThreads {
+            name: Some("no\0pe".to_string()),
+            ..Default::default()
+        };
+        assert!(threads.os_name().is_none());
+    }
+
+    #[test]
+    fn descriptor_counts_are_never_truncated() {
+        assert_eq!(desc_num(0).unwrap(), 0);
+        assert_eq!(desc_num(u32::MAX as usize).unwrap(), u32::MAX);
+        let error = desc_num(u32::MAX as usize + 1).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+
+        let fds = [DoorFd::new(0, false), DoorFd::new(1, false)];
+        let arg = DoorArg::try_new(b"data", &fds, &mut [0; 8]).unwrap();
+        assert_eq!(arg.arg.desc_num, 2);
+        assert_eq!(arg.arg.data_size, 4);
+        assert_eq!(arg.arg.rsize, 8);
+    }
+
+    #[test]
+    fn door_fds_deduplicate() {
+        let mut set = std::collections::HashSet::new();
+        assert!(set.insert(DoorFd::new(3, false)));
+        assert!(!set.insert(DoorFd::new(3, false)));
+        assert!(set.insert(DoorFd::new(3, true)));
+        assert!(set.insert(DoorFd::new(4, false)));
+        assert_eq!(set.len(), 3);
+    }
+
+    #[test]
+    fn door_attributes_default_to_none() {
+        assert_eq!(DoorAttributes::default(), DoorAttributes::none());
+        assert!(DoorAttributes::default().is_empty());
+    }
+
+    #[test]
+    fn door_attributes_insert_remove_toggle() {
+        let mut attrs = DoorAttributes::none();
+        attrs.insert(DoorAttributes::unref());
+        attrs.insert(DoorAttributes::private());
+        assert!(attrs.contains(DoorAttributes::unref()));
+        assert!(
+            attrs.contains(DoorAttributes::unref() | DoorAttributes::private())
+        );
+
+        attrs.remove(DoorAttributes::unref());
+        assert!(!attrs.contains(DoorAttributes::unref()));
+        assert_eq!(attrs, DoorAttributes::private());
+
+        attrs.toggle(DoorAttributes::private() | DoorAttributes::no_cancel());
+        assert_eq!(attrs, DoorAttributes::no_cancel());
+    }
+
+    #[test]
+    fn door_attributes_operators() {
+        let both = DoorAttributes::unref() | DoorAttributes::refuse_desc();
+        assert!(both.intersects(DoorAttributes::refuse_desc()));
+        assert!(!both.intersects(DoorAttributes::private()));
+        assert_eq!(both & DoorAttributes::unref(), DoorAttributes::unref());
+        assert_eq!(
+            both - DoorAttributes::unref(),
+            DoorAttributes::refuse_desc()
+        );
+
+        let mut attrs = both;
+        attrs &= DoorAttributes::refuse_desc();
+        assert_eq!(attrs, DoorAttributes::refuse_desc());
+        attrs -= DoorAttributes::refuse_desc();
+        assert!(attrs.is_empty());
+    }
+
+    #[test]
+    fn door_info_ignores_reserved_bytes() {
+        let info = DoorInfo::default();
+        let mut raw = info.0;
+        raw.di_resv = [1, 2, 3, 4];
+        let other = DoorInfo(raw);
+
+        assert_eq!(info, other);
+        assert_eq!(format!("{:?}", info), format!("{:?}", other));
+        assert_eq!(other.raw_reserved(), [1, 2, 3, 4]);
+    }
+
     #[test]
     fn as_raw_fd() {
         let dd = DoorFd::new(-1, true);
@@ -0,0 +1,33 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ *
+ * Copyright 2023 Robert D. French
+ */
+
+//! Unsafe Declarations for the illumos Thread Naming API
+//!
+//! This module merely re-exports the subset of the illumos pthread api that we
+//! need for this project. It makes no attempt at safety or ergonomics.
+//!
+//! Door servers name the threads of their private pools with these, so that
+//! tools like `pstack` and `mdb` can tell them apart.
+
+/// Longest thread name, counting the terminating NUL
+pub const PTHREAD_MAX_NAMELEN_NP: usize = 32;
+
+extern "C" {
+    /// Give a thread a name.
+    ///
+    /// Fails with `ERANGE`, leaving the name alone, if `name` is longer than
+    /// [`PTHREAD_MAX_NAMELEN_NP`] allows.
+    ///
+    /// See [`PTHREAD_SETNAME_NP(3C)`] for more details.
+    ///
+    /// [`PTHREAD_SETNAME_NP(3C)`]: https://illumos.org/man/3c/pthread_setname_np
+    pub fn pthread_setname_np(
+        thread: libc::pthread_t,
+        name: *const libc::c_char,
+    ) -> libc::c_int;
+}
@@ -0,0 +1,49 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ *
+ * Copyright 2023 Robert D. French
+ */
+
+//! Unsafe Declarations for the illumos User Credentials API
+//!
+//! This module merely re-exports the subset of the illumos ucred api that we
+//! need for this project. It makes no attempt at safety or ergonomics.
+//!
+//! A door server asks for its client's credentials with
+//! [`door_ucred`][super::door_h::door_ucred], and picks them apart with the
+//! functions here.
+
+/// Opaque user credentials
+///
+/// Only ever handled through a pointer, which must eventually be passed to
+/// [`ucred_free`].
+#[repr(C)]
+pub struct ucred_t {
+    _private: [u8; 0],
+}
+
+extern "C" {
+    /// Release credentials allocated by `door_ucred`.
+    ///
+    /// See [`UCRED_GET(3C)`] for more details.
+    ///
+    /// [`UCRED_GET(3C)`]: https://illumos.org/man/3C/ucred_get
+    pub fn ucred_free(uc: *mut ucred_t);
+
+    /// The effective user id, or `(uid_t)-1` if it is not available.
+    pub fn ucred_geteuid(uc: *const ucred_t) -> libc::uid_t;
+
+    /// The effective group id, or `(gid_t)-1` if it is not available.
+    pub fn ucred_getegid(uc: *const ucred_t) -> libc::gid_t;
+
+    /// The process id, or `-1` if it is not available.
+    pub fn ucred_getpid(uc: *const ucred_t) -> libc::pid_t;
+
+    /// The zone id, or `-1` if it is not available.
+    pub fn ucred_getzoneid(uc: *const ucred_t) -> libc::c_int;
+
+    /// The audit session id, or `-1` if it is not available.
+    pub fn ucred_getasid(uc: *const ucred_t) -> libc::pid_t;
+}
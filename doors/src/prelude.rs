@@ -0,0 +1,38 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ *
+ * Copyright 2023 Robert D. French
+ */
+//! The types most door clients and servers need, in one place.
+//!
+//! ```
+//! use doors::prelude::*;
+//!
+//! #[server_procedure]
+//! fn double(x: Request<'_>) -> Response<[u8; 1]> {
+//!     Response::new([x.data[0] * 2])
+//! }
+//!
+//! let door = Door::create(double).unwrap();
+//! let response = door.call_self(DoorArgument::new(&[111], &[], &mut [])).unwrap();
+//! assert_eq!(response.data(), &[222]);
+//! ```
+//!
+//! Both [`crate::server::Error`] and [`crate::illumos::Error`] are left out,
+//! since glob-importing either would shadow any other `Error` in scope, as
+//! are the raw bindings in [`crate::illumos`]. [`DoorFd`] is the exception:
+//! it is how descriptors are passed in either direction.
+
+pub use crate::illumos::DoorFd;
+pub use crate::server::Door;
+pub use crate::server::DoorServer;
+pub use crate::server::Request;
+pub use crate::server::Response;
+pub use crate::server::Serve;
+pub use crate::server_procedure;
+pub use crate::Client;
+pub use crate::DoorArgument;
+pub use crate::DoorCallError;
+pub use crate::DoorError;
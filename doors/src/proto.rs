@@ -0,0 +1,107 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ *
+ * Copyright 2023 Robert D. French
+ */
+//! Protocol Buffers messages, sent through doors.
+//!
+//! With the `prost` feature, any [`prost::Message`] can be sent as a request
+//! or a response. Messages travel length-delimited: a varint giving the
+//! length of the encoded message, followed by the message itself. That is the
+//! framing protobuf uses for streams, so a peer written in another language
+//! can decode it with its own library.
+//!
+//! On the server, [`Request::message`] decodes the request, and
+//! [`Response::message`] encodes the reply. On the client,
+//! [`Client::call_message`] does both halves of the call at once.
+//!
+//! ```
+//! use doors::server::{Request, Response};
+//!
+//! #[derive(Clone, PartialEq, prost::Message)]
+//! struct Greeting {
+//!     #[prost(string, tag = "1")]
+//!     name: String,
+//! }
+//!
+//! #[doors::server_procedure]
+//! fn greet(x: Request<'_>) -> Response<Vec<u8>> {
+//!     match x.message::<Greeting>() {
+//!         Ok(greeting) => Response::message(&Greeting {
+//!             name: format!("hello, {}", greeting.name),
+//!         }),
+//!         Err(e) => Response::err_bytes(1, e.to_string().as_bytes()),
+//!     }
+//! }
+//! ```
+
+use crate::server::Request;
+use crate::server::Response;
+use crate::Client;
+use crate::DoorArgument;
+use crate::DoorCallError;
+use crate::DoorError;
+use prost::DecodeError;
+use prost::Message;
+
+/// Ways in which [`Client::call_message`] can fail
+#[derive(Debug)]
+pub enum MessageCallError {
+    /// The door call itself failed.
+    Call(DoorCallError),
+
+    /// The server answered with an error frame, rather than a message.
+    Server(DoorError),
+
+    /// The response could not be decoded as the message type expected.
+    Decode(DecodeError),
+}
+
+impl From<DoorCallError> for MessageCallError {
+    fn from(e: DoorCallError) -> Self {
+        Self::Call(e)
+    }
+}
+
+impl<S> Request<'_, S> {
+    /// Decode this request as a length-delimited `M`.
+    pub fn message<M: Message + Default>(&self) -> Result<M, DecodeError> {
+        M::decode_length_delimited(self.data)
+    }
+}
+
+impl Response<Vec<u8>> {
+    /// Answer with `message`, length-delimited.
+    pub fn message<M: Message>(message: &M) -> Self {
+        Self::new(message.encode_length_delimited_to_vec())
+    }
+}
+
+impl Client {
+    /// Send a message, and decode the message which comes back.
+    ///
+    /// The request is encoded length-delimited, and so must the response be,
+    /// as [`Response::message`] does. Error frames are reported as
+    /// [`MessageCallError::Server`]. The response is decoded straight out of
+    /// wherever the kernel put it, and any descriptors sent with it are
+    /// closed.
+    pub fn call_message<Q, R>(&self, request: &Q) -> Result<R, MessageCallError>
+    where
+        Q: Message,
+        R: Message + Default,
+    {
+        let data = request.encode_length_delimited_to_vec();
+        let mut arg = DoorArgument::new(&data, &[], &mut []);
+        arg.ensure_rbuf_capacity(self.default_rbuf());
+        let response = self.call(arg)?;
+        let decoded = match response.as_result() {
+            Ok(data) => R::decode_length_delimited(data)
+                .map_err(MessageCallError::Decode),
+            Err(e) => Err(MessageCallError::Server(e)),
+        };
+        drop(response.into_descriptors());
+        decoded
+    }
+}
@@ -0,0 +1,211 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ *
+ * Copyright 2023 Robert D. French
+ */
+//! Async door handlers, run on a Tokio runtime.
+//!
+//! This module is only available with the `tokio` feature enabled. Most Rust
+//! services are async, and their handlers want to `.await` database queries
+//! and other I/O. Door calls, however, arrive on door threads, which the
+//! kernel expects to block until they have an answer. An [`AsyncDoor`]
+//! bridges the two.
+//!
+//! ## Threading Model
+//!
+//! Each call is handled in three steps:
+//!
+//! 1. The kernel hands the call to a door thread, as for any door. The door
+//!    thread copies the request's data into an [`AsyncRequest`], along with
+//!    ownership of any descriptors that came with it. The original request is
+//!    about to be overwritten by the response, so nothing is borrowed from
+//!    it.
+//! 2. The door thread spawns [`AsyncServe::serve`] onto the runtime as a task
+//!    of its own, and then blocks, waiting for that task to finish. The
+//!    handler runs on the runtime's worker threads, never on the door thread,
+//!    and may `.await` whatever it likes.
+//! 3. Once the task finishes, the door thread wakes up and returns its
+//!    response to the client with `door_return`.
+//!
+//! So every call in progress holds one door thread, asleep, and one task on
+//! the runtime. The door's thread pool is managed by the kernel and the door
+//! library as usual, and grows to match the number of concurrent calls; the
+//! runtime's worker threads are shared with the rest of the program.
+//!
+//! The runtime must be able to make progress without the door threads' help:
+//! use a multi-threaded runtime, or a current-thread runtime whose thread sits
+//! in [`Runtime::block_on`][tokio::runtime::Runtime::block_on] for as long as
+//! the door is in use. Nothing else works, since a door thread only ever
+//! waits.
+//!
+//! A handler which panics is reported to its client as with any other server
+//! procedure, with an error frame whose code is
+//! [`DoorError::SERVER_PANICKED`][crate::DoorError::SERVER_PANICKED]. If the
+//! runtime shuts down with calls in progress, their tasks are cancelled, and
+//! the clients are answered with [`Response::unavailable`].
+//!
+//! ```no_run
+//! use doors::runtime::{AsyncDoor, AsyncRequest, AsyncServe};
+//! use doors::server::Response;
+//! use std::sync::Arc;
+//! use std::time::Duration;
+//!
+//! struct Slow;
+//!
+//! impl AsyncServe for Slow {
+//!     async fn serve(
+//!         self: Arc<Self>,
+//!         request: AsyncRequest,
+//!     ) -> Response<Vec<u8>> {
+//!         tokio::time::sleep(Duration::from_millis(10)).await;
+//!         Response::new(request.data)
+//!     }
+//! }
+//!
+//! let runtime = tokio::runtime::Runtime::new().unwrap();
+//! let door = AsyncDoor::new(Slow, runtime.handle().clone()).unwrap();
+//! door.door().force_install("/tmp/slow.door").unwrap();
+//! ```
+
+use crate::illumos;
+use crate::illumos::door_h::door_desc_t;
+use crate::server::Door;
+use crate::server::Error;
+use crate::server::Invocation;
+use crate::server::Request;
+use crate::server::Response;
+use std::future::Future;
+use std::os::fd::FromRawFd;
+use std::os::fd::OwnedFd;
+use std::sync::Arc;
+use tokio::runtime::Handle;
+
+/// A door call, owned so that it can be handed to an async task
+pub struct AsyncRequest {
+    /// The data the client sent.
+    pub data: Vec<u8>,
+
+    /// The descriptors the client sent, in order.
+    ///
+    /// The kernel gave these to this process along with the call, so they are
+    /// closed when dropped, like any other [`OwnedFd`].
+    pub descriptors: Vec<OwnedFd>,
+}
+
+/// Shared state which answers door calls asynchronously
+///
+/// This is the async counterpart to [`Serve`][crate::server::Serve]: every
+/// call to an [`AsyncDoor`] is passed to [`AsyncServe::serve`] on the
+/// runtime, with a handle to the state it was created with.
+pub trait AsyncServe: Send + Sync + 'static {
+    /// Answer a single door call.
+    fn serve(
+        self: Arc<Self>,
+        request: AsyncRequest,
+    ) -> impl Future<Output = Response<Vec<u8>>> + Send;
+}
+
+/// Everything a door thread needs to hand a call to the runtime.
+struct Bridge<S> {
+    state: Arc<S>,
+    handle: Handle,
+}
+
+/// A door whose calls are answered by async handlers
+///
+/// See the [module documentation][self] for how calls are passed between the
+/// door's threads and the runtime. Like a
+/// [`DoorServer`][crate::server::DoorServer], an `AsyncDoor` never frees its
+/// state, since its door may still be answering calls after it has been
+/// revoked; a server typically lives as long as its process anyway.
+pub struct AsyncDoor<S: AsyncServe> {
+    state: Arc<S>,
+    door: Door,
+}
+
+impl<S: AsyncServe> AsyncDoor<S> {
+    /// Create a door which spawns each call to `state` on the runtime that
+    /// `handle` refers to.
+    ///
+    /// The door is not installed anywhere; do that with [`AsyncDoor::door`].
+    pub fn new(state: S, handle: Handle) -> Result<Self, Error> {
+        let state = Arc::new(state);
+        let bridge = Box::new(Bridge {
+            state: state.clone(),
+            handle,
+        });
+        let cookie = Box::into_raw(bridge);
+        match Door::create_with_cookie(dispatch::<S>, cookie as u64) {
+            Ok(door) => Ok(Self { state, door }),
+            Err(e) => {
+                // The door was never created, so nothing refers to the bridge.
+                drop(unsafe { Box::from_raw(cookie) });
+                Err(e)
+            }
+        }
+    }
+
+    /// The state which answers this door's calls.
+    pub fn state(&self) -> &Arc<S> {
+        &self.state
+    }
+
+    /// The door itself, to be installed or passed to clients.
+    pub fn door(&self) -> &Door {
+        &self.door
+    }
+}
+
+/// Take ownership of every descriptor that came with a call.
+fn own_descriptors(descriptors: &[door_desc_t]) -> Vec<OwnedFd> {
+    descriptors
+        .iter()
+        .map(|desc| {
+            let fd = unsafe { desc.d_data.d_desc.d_descriptor };
+            unsafe { OwnedFd::from_raw_fd(fd) }
+        })
+        .collect()
+}
+
+extern "C" fn dispatch<S: AsyncServe>(
+    cookie: *const libc::c_void,
+    argp: *const libc::c_char,
+    arg_size: libc::size_t,
+    dp: *const door_desc_t,
+    n_desc: libc::c_uint,
+) {
+    if argp == illumos::door_h::DOOR_UNREF_DATA {
+        Response::<[u8; 0]>::empty().door_return()
+    }
+    let bridge = unsafe { &*(cookie as *const Bridge<S>) };
+    let data = match arg_size {
+        0 => &[],
+        _ => unsafe { std::slice::from_raw_parts(argp as *const u8, arg_size) },
+    };
+    let descriptors = match n_desc {
+        0 => &[],
+        _ => unsafe { std::slice::from_raw_parts(dp, n_desc as usize) },
+    };
+    let request = Request {
+        cookie: cookie as u64,
+        data,
+        descriptors,
+        state: &(),
+    };
+    let invocation = Invocation::enter(std::any::type_name::<S>(), &request);
+
+    let owned = AsyncRequest {
+        data: data.to_vec(),
+        descriptors: own_descriptors(descriptors),
+    };
+    let task = bridge.handle.spawn(bridge.state.clone().serve(owned));
+    let response = match bridge.handle.block_on(task) {
+        Ok(response) => response,
+        Err(e) if e.is_panic() => Response::from_panic(e.into_panic()),
+        Err(_) => Response::unavailable(),
+    };
+    invocation.exit();
+    response.door_return()
+}
@@ -12,12 +12,38 @@ use crate::illumos::door_h::door_desc_t;
 use crate::illumos::fattach;
 use crate::illumos::DoorAttributes;
 use crate::illumos::DoorFd;
+use crate::Client;
 use libc;
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::ffi;
 use std::fs::File;
 use std::io;
+use std::io::Read;
+use std::io::Write;
+use std::marker::PhantomData;
+use std::mem::ManuallyDrop;
+use std::os::fd::AsRawFd;
+use std::os::fd::BorrowedFd;
+use std::os::fd::FromRawFd;
+use std::os::fd::IntoRawFd;
+use std::os::fd::OwnedFd;
 use std::os::fd::RawFd;
+use std::os::unix::ffi::OsStrExt;
+use std::panic;
+use std::panic::AssertUnwindSafe;
 use std::path::Path;
+use std::path::PathBuf;
+use std::ptr;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::sync::Condvar;
+use std::sync::Mutex;
+use std::thread;
 
 /// Door problems.
 ///
@@ -25,6 +51,23 @@ use std::path::Path;
 /// call can fail. If a system call fails, one of this enum's variants will be
 /// returned corresponding to the failed system call. It will contain the value
 /// of `errno` associated with the failed system call.
+///
+/// ## Running out of descriptors
+///
+/// Every door occupies a slot in the process's descriptor table, so a server
+/// which creates many doors can run into its `RLIMIT_NOFILE`. That shows up as
+/// `CreateDoor(illumos::Error::EMFILE)`, and nothing will have been created. The
+/// soft limit can be raised as far as the hard limit with [`SETRLIMIT(2)`], and
+/// the door created again:
+///
+/// ```no_run
+/// let mut limit = libc::rlimit { rlim_cur: 0, rlim_max: 0 };
+/// unsafe { libc::getrlimit(libc::RLIMIT_NOFILE, &mut limit) };
+/// limit.rlim_cur = limit.rlim_max;
+/// unsafe { libc::setrlimit(libc::RLIMIT_NOFILE, &limit) };
+/// ```
+///
+/// [`SETRLIMIT(2)`]: https://illumos.org/man/2/setrlimit
 #[derive(Debug)]
 pub enum Error {
     InvalidPath(ffi::NulError),
@@ -33,6 +76,13 @@ pub enum Error {
     OpenDoor(std::io::Error),
     DoorCall(libc::c_int),
     CreateDoor(illumos::Error),
+    Cloexec(std::io::Error),
+    SpawnThread(std::io::Error),
+    BindThread(illumos::Error),
+    CreateParent(std::io::Error),
+    CloneDoor(std::io::Error),
+    DetachDoor(illumos::Error),
+    QueryDoor(illumos::Error),
 }
 
 /// A Descriptor for the Door Server
@@ -40,7 +90,7 @@ pub enum Error {
 /// When a door is created, the kernel hands us back a reference to it by giving
 /// us an index in our descriptor table. This is true even if the door hasn't
 /// been attached to the filesystem yet, a la pipes or sockets.
-pub struct Door(RawFd);
+pub struct Door(RawFd, Option<&'static dyn Release>);
 
 impl Door {
     /// Create a new Door with the specified server procedure.  This will not
@@ -75,18 +125,289 @@ impl Door {
         Self::create_with_cookie_and_attributes(sp, cookie, attrs)
     }
 
+    /// Create a new Door whose cookie points to some shared state.  This will
+    /// not expose the door to the filesystem by default.
+    ///
+    /// The server procedure must have been declared with
+    /// `#[server_procedure(state = S)]` for the same `S`, which the compiler
+    /// will check. Every invocation may read the state concurrently, from
+    /// whichever door thread answers it, hence the `Sync` bound; and since the
+    /// door may be called at any time, the state must live forever.
+    pub fn create_with_state<S: Sync>(
+        sp: StatefulProcedure<S>,
+        state: &'static S,
+    ) -> Result<Self, Error> {
+        let cookie = state as *const S as u64;
+        Self::create_with_cookie(sp.sp, cookie)
+    }
+
+    /// Create a new Door which shares ownership of some state.  This will not
+    /// expose the door to the filesystem by default.
+    ///
+    /// This is [`Door::create_with_state`] for state which should not live
+    /// forever. The server procedure must have been declared with
+    /// `#[server_procedure(state = ArcState<T>)]`, and handlers get their own
+    /// reference to the state from [`Request::arc_state`]. When the door is
+    /// dropped (and so revoked), it gives up its reference, and the state is
+    /// dropped along with the last one. See [`ArcState`] for how calls still
+    /// in progress at that point are kept safe.
+    pub fn create_with_arc<T: Send + Sync + 'static>(
+        sp: StatefulProcedure<ArcState<T>>,
+        state: Arc<T>,
+    ) -> Result<Self, Error> {
+        let slot: &'static ArcState<T> =
+            Box::leak(Box::new(ArcState(Mutex::new(Some(state)))));
+        match Self::create_with_state(sp, slot) {
+            Ok(mut door) => {
+                door.1 = Some(slot);
+                Ok(door)
+            }
+            Err(e) => {
+                slot.release();
+                Err(e)
+            }
+        }
+    }
+
     /// Create a new Door with Cookie and Attributes.  This will not expose the
     /// door to the filesystem by default. It will use the [`DoorAttributes`]
     /// and cookie that you provide.
+    ///
+    /// Like every door created through this type, the descriptor will have
+    /// `FD_CLOEXEC` set. See [`Door::set_cloexec`] if you need to hand it to a
+    /// child process across an `exec`.
     pub fn create_with_cookie_and_attributes(
         sp: illumos::ServerProcedure,
         cookie: u64,
         attrs: illumos::DoorAttributes,
     ) -> Result<Self, Error> {
-        match illumos::door_create(sp, cookie, attrs) {
-            Ok(fd) => Ok(Self(fd as RawFd)),
-            Err(e) => Err(Error::CreateDoor(e)),
+        Self::builder().cookie(cookie).attributes(attrs).build(sp)
+    }
+
+    /// Start describing a new door, one option at a time.
+    ///
+    /// The `create_*` constructors each cover one combination of options;
+    /// [`DoorBuilder`] covers all of them:
+    ///
+    /// ```no_run
+    /// use doors::illumos::DoorAttributes;
+    /// use doors::server::{Door, Request, Response};
+    ///
+    /// #[doors::server_procedure]
+    /// fn hello(_x: Request<'_>) -> Response<[u8; 5]> {
+    ///     Response::new(*b"hello")
+    /// }
+    ///
+    /// let door = Door::builder()
+    ///     .cookie(42)
+    ///     .attributes(DoorAttributes::refuse_desc())
+    ///     .private_pool(4)
+    ///     .build(hello)
+    ///     .unwrap();
+    /// ```
+    pub fn builder() -> DoorBuilder {
+        DoorBuilder::default()
+    }
+
+    /// Take charge of a freshly created door descriptor, marking it
+    /// `FD_CLOEXEC`.
+    fn adopt(fd: RawFd) -> Result<Self, Error> {
+        let door = Self(fd, None);
+        match door.set_cloexec(true) {
+            Ok(()) => Ok(door),
+            Err(e) => Err(Error::Cloexec(e)),
+        }
+    }
+
+    /// Create a door served by threads made as `threads` describes. See
+    /// [`illumos::door_xcreate`].
+    ///
+    /// The recipe is kept in a slot which the door clears once it is revoked,
+    /// so that only the slot itself outlives the door.
+    fn xcreate(
+        sp: illumos::ServerProcedure,
+        cookie: u64,
+        attrs: DoorAttributes,
+        threads: illumos::DoorThreads,
+        nthread: usize,
+    ) -> Result<Self, Error> {
+        let slot: &'static illumos::DoorThreadsSlot =
+            Box::leak(Box::new(illumos::DoorThreadsSlot::new(threads)));
+        let door = illumos::door_xcreate(sp, cookie, attrs, slot, nthread)
+            .map_err(Error::CreateDoor)
+            .and_then(Self::adopt);
+        match door {
+            Ok(mut door) => {
+                door.1 = Some(slot);
+                Ok(door)
+            }
+            Err(e) => {
+                slot.clear();
+                Err(e)
+            }
+        }
+    }
+
+    /// Make sure a newly created door answers a call at all.
+    ///
+    /// `door_create` cannot tell whether the function it was given really has
+    /// the signature of a [`ServerProcedure`][illumos::ServerProcedure]; one
+    /// which was transmuted into place only crashes once it is called. With
+    /// the `abi-check` feature, debug builds call every new door once, with no
+    /// data and no descriptors, so that such a mistake crashes at creation
+    /// instead of somewhere far away. This is a development aid, not a
+    /// production check: the procedure really is invoked, so it must cope with
+    /// an empty request, and a gross mistake still takes the process down.
+    /// Private doors are skipped: the threads of a
+    /// [`DoorBuilder::private_pool`] are not yet bound, and a call to a pool
+    /// made with [`DoorBuilder::stack_size`] and friends would set off its
+    /// depletion callback.
+    #[cfg(all(feature = "abi-check", debug_assertions))]
+    fn smoke_test(&self) {
+        let arg = crate::DoorArgument::new(&[], &[], &mut []);
+        match self.call_self(arg) {
+            Ok(response) => drop(response.into_descriptors()),
+            Err(e) => panic!("new door did not answer a smoke test: {:?}", e),
+        }
+    }
+
+    /// Create a new Door served by a private pool of `pool_size` threads.
+    ///
+    /// The door is created with [`DoorAttributes::private`], and each thread in
+    /// the pool binds itself to the door with [`door_bind`][illumos::door_bind]
+    /// before waiting for invocations. Every call to this door will be answered
+    /// by one of these threads, which are already warm by the time this
+    /// function returns. Should the pool be exhausted, any additional threads
+    /// come from the process-wide `door_server_create` function, just as they
+    /// would for any other door.
+    ///
+    /// The pool threads exit once the door has been revoked: a thread bound to a
+    /// door that no longer exists is sent back from `door_return` with
+    /// [`illumos::Error::DoorGone`]. See [`illumos::door_wait`] if you are
+    /// managing bound threads yourself.
+    pub fn create_private(
+        sp: illumos::ServerProcedure,
+        pool_size: usize,
+    ) -> Result<Self, Error> {
+        Self::builder().private_pool(pool_size).build(sp)
+    }
+
+    /// Spawn `pool_size` threads bound to this private door, and wait until
+    /// each of them is ready to answer calls.
+    fn bind_pool(self, pool_size: usize) -> Result<Self, Error> {
+        let (tx, rx) = mpsc::channel();
+        for _ in 0..pool_size {
+            let tx = tx.clone();
+            let fd = self.0;
+            let spawned = thread::Builder::new().spawn(move || {
+                let bound = illumos::door_bind(fd);
+                let ok = bound.is_ok();
+                tx.send(bound).ok();
+                drop(tx);
+                if ok {
+                    // This only comes back once the door is gone, with
+                    // Error::DoorGone, at which point the thread is done.
+                    illumos::door_wait();
+                }
+            });
+            if let Err(e) = spawned {
+                return Err(Error::SpawnThread(e));
+            }
+        }
+        drop(tx);
+
+        for bound in rx {
+            if let Err(e) = bound {
+                return Err(Error::BindThread(e));
+            }
         }
+        Ok(self)
+    }
+
+    /// Duplicate this door's descriptor.
+    ///
+    /// The clone refers to the same door, so it can be installed at a second
+    /// path with [`Door::install`] and calls through either path will reach the
+    /// same server procedure.
+    ///
+    /// Be aware that revocation applies to the door, not to the descriptor:
+    /// when *any* clone is dropped, [`door_revoke`][illumos::door_h::door_revoke]
+    /// shuts the door for every descriptor that refers to it, clones included.
+    /// Keep all of the clones alive for as long as the door should be served.
+    pub fn try_clone(&self) -> io::Result<Self> {
+        let fd = unsafe { BorrowedFd::borrow_raw(self.0) };
+        let fd = fd.try_clone_to_owned()?;
+        Ok(Self(fd.into_raw_fd(), self.1))
+    }
+
+    /// Call this door from within the same process.
+    ///
+    /// Door descriptors are directly callable, so a server can invoke its own
+    /// doors without opening them on the filesystem. This works from inside a
+    /// server procedure, too: the nested call is answered by a different door
+    /// thread while the calling thread blocks.
+    ///
+    /// That is also the pitfall. If no other thread is available to answer --
+    /// for example, a door created with [`Door::create_private`] and a pool of
+    /// one, calling itself from its own handler -- the nested call will wait
+    /// forever.
+    pub fn call_self(
+        &self,
+        arg: crate::DoorArgument,
+    ) -> Result<crate::DoorArgument, crate::DoorCallError> {
+        // Borrow our descriptor as a client, without letting it close it.
+        let client = ManuallyDrop::new(unsafe { Client::from_raw_fd(self.0) });
+        client.call(arg)
+    }
+
+    /// A [`Client`] for this door, without installing it anywhere.
+    ///
+    /// The client gets a duplicate of the door's descriptor, which is directly
+    /// callable, so handlers can be tested entirely in-process: no `fattach`,
+    /// no files in `/tmp`, and no server launched beforehand. Unlike
+    /// [`Door::call_self`], the client owns its descriptor and may be kept or
+    /// handed to another thread; closing it does not revoke the door. Once
+    /// this `Door` is dropped, calls through the client fail with
+    /// [`DoorCallError::Revoked`][crate::DoorCallError::Revoked].
+    pub fn loopback_client(&self) -> io::Result<Client> {
+        let fd = unsafe { BorrowedFd::borrow_raw(self.0) };
+        let fd = fd.try_clone_to_owned()?;
+        Ok(unsafe { Client::from_raw_fd(fd.into_raw_fd()) })
+    }
+
+    /// Look up what the kernel knows about this door.
+    ///
+    /// This is [`door_info`][illumos::door_info] on the server's own
+    /// descriptor, so server code can check that its door is wired to the
+    /// procedure, cookie, and attributes it expects.
+    pub fn info(&self) -> Result<illumos::DoorInfo, Error> {
+        illumos::door_info(self.0).map_err(Error::QueryDoor)
+    }
+
+    /// Whether anyone besides this server holds a descriptor for the door.
+    ///
+    /// This is for servers which want to drain before shutting down. It reads
+    /// the kernel's `DOOR_IS_UNREF` flag with [`Door::info`], which counts
+    /// every descriptor for the door other than the server's own: clients'
+    /// descriptors, the door's attachment to the filesystem (see
+    /// [`Door::install`]), and clients made with [`Door::loopback_client`]
+    /// all make it referenced.
+    ///
+    /// The answer is a snapshot. A client may open the door, or close it, the
+    /// moment after this returns, so "not referenced" is only a promise that
+    /// nobody was connected at the time of asking. Detach the door from the
+    /// filesystem first, so that no new clients can find it, and poll.
+    pub fn is_referenced(&self) -> Result<bool, Error> {
+        Ok(!self.info()?.is_unreferenced())
+    }
+
+    /// Control whether this door's descriptor survives an `exec`.
+    ///
+    /// Doors are created with `FD_CLOEXEC` set, so that they do not leak into
+    /// child processes. Pass `false` here for the rare server which deliberately
+    /// passes its door descriptor to a program it `exec`s.
+    pub fn set_cloexec(&self, cloexec: bool) -> io::Result<()> {
+        illumos::set_cloexec(self.0, cloexec)
     }
 
     /// Make this door server available on the filesystem.  This is necessary if
@@ -109,15 +430,334 @@ impl Door {
         }
     }
 
+    /// Stop new clients from finding this door at `path`, and close `gate`.
+    ///
+    /// The door is detached from its jamb, which stays behind for
+    /// [`Door::resume`] to reattach it to. Clients which already have the door
+    /// open can still call it, so handlers should check `gate` and answer with
+    /// [`Response::unavailable`] while it is closed. Calls already in progress
+    /// are not interrupted; they finish as usual.
+    pub fn suspend<P: AsRef<Path>>(
+        &self,
+        path: P,
+        gate: &Gate,
+    ) -> Result<(), Error> {
+        gate.0.store(false, Ordering::SeqCst);
+        illumos::fdetach(path).map_err(Error::DetachDoor)
+    }
+
+    /// Undo [`Door::suspend`]: reattach this door at `path`, and open `gate`.
+    pub fn resume<P: AsRef<Path>>(
+        &self,
+        path: P,
+        gate: &Gate,
+    ) -> Result<(), Error> {
+        fattach(self.0, path).map_err(Error::AttachDoor)?;
+        gate.0.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+
+    /// Serve calls until `shutdown` is requested, then take the door down.
+    ///
+    /// This is for the end of a server's `main`, in place of sleeping forever.
+    /// Once [`Shutdown::request`] has been called, whether by a handler or by
+    /// some other thread, the door is detached from `path`, the jamb is
+    /// removed, and the door is revoked. Calls already in progress finish as
+    /// usual. If the door cannot be detached, it is still revoked, and the
+    /// error is returned.
+    pub fn wait_for_shutdown<P: AsRef<Path>>(
+        self,
+        path: P,
+        shutdown: &Shutdown,
+    ) -> Result<(), Error> {
+        shutdown.wait();
+        uninstall(path.as_ref()).map_err(Error::DetachDoor)
+    }
+
+    /// Make this door server available on the filesystem, creating any missing
+    /// parent directories first.
+    ///
+    /// If a directory cannot be created (say, because some component of the
+    /// path is a regular file), this fails with [`Error::CreateParent`] before
+    /// attempting the install.
+    pub fn install_with_parents<P: AsRef<Path>>(
+        &self,
+        path: P,
+    ) -> Result<(), Error> {
+        if let Some(parent) = path.as_ref().parent() {
+            if let Err(e) = std::fs::create_dir_all(parent) {
+                return Err(Error::CreateParent(e));
+            }
+        }
+        self.install(path)
+    }
+
     /// Make this door available on the filesystem even if there is already a
     /// file (possibly leftover from a previous door) as this path.
+    ///
+    /// This is meant for redeploying a server while the old one is still
+    /// answering, so clients should never find `path` without a door. That
+    /// rules out removing whatever is there and installing afresh. Nor can
+    /// the door be attached somewhere else and renamed into place: a door is
+    /// attached to a path by mounting over it, and the kernel refuses to
+    /// rename a mount point or to rename anything over one.
+    ///
+    /// Instead, the door is attached to a hidden jamb beside `path`, named
+    /// `.<file name>.<pid>.<n>`, and `path` becomes a symbolic link to it.
+    /// Swapping one link for another *can* be done with a rename, so when
+    /// `path` is already such a link, clients opening it find either the old
+    /// door or the new one, and never nothing. The old door's jamb is then
+    /// removed, which detaches it; clients which already have it open are
+    /// unaffected.
+    ///
+    /// Only the first switch away from a door installed at `path` itself (say,
+    /// with [`Door::install`]) leaves a gap, since that door must be detached
+    /// before the link can take its place. The gap is two system calls wide.
     pub fn force_install<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
-        if path.as_ref().exists() {
-            if let Err(e) = std::fs::remove_file(&path) {
+        let path = path.as_ref();
+        let mut name = match jamb_prefix(path) {
+            Some(prefix) => prefix,
+            None => {
+                let e = io::Error::from(io::ErrorKind::InvalidInput);
                 return Err(Error::InstallJamb(e));
             }
+        };
+        let n = JAMBS.fetch_add(1, Ordering::Relaxed);
+        name.extend_from_slice(
+            format!("{}.{n}", std::process::id()).as_bytes(),
+        );
+        let jamb = path.with_file_name(ffi::OsStr::from_bytes(&name));
+
+        // Anything here was left by an earlier process with the same pid.
+        std::fs::remove_file(&jamb).ok();
+        self.install(&jamb)?;
+
+        let old = linked_jamb(path);
+        if let Err(e) = swap_link(ffi::OsStr::from_bytes(&name), path) {
+            // Removing the jamb detaches the door from it, too.
+            std::fs::remove_file(&jamb).ok();
+            return Err(Error::InstallJamb(e));
         }
-        self.install(path)
+        if let Some(old) = old {
+            std::fs::remove_file(old).ok();
+        }
+        Ok(())
+    }
+
+    /// Move this door from the path it is installed at to `new_path`.
+    ///
+    /// The door is installed at `new_path` first, and only then detached from
+    /// `old_path` and its jamb removed, so there is never a moment when
+    /// neither path leads to the door. Instead there is a brief window in
+    /// which both do, and a client may open the door at either. Clients that
+    /// already have it open are unaffected.
+    ///
+    /// If the door cannot be installed at `new_path`, it is left where it was.
+    /// If it then cannot be detached from `old_path`, it is left at both, and
+    /// the error is returned.
+    pub fn reinstall<P: AsRef<Path>, Q: AsRef<Path>>(
+        &self,
+        old_path: P,
+        new_path: Q,
+    ) -> Result<(), Error> {
+        self.reinstall_with(old_path.as_ref(), new_path.as_ref(), || ())
+    }
+
+    /// [`Door::reinstall`], calling `between` while both paths lead to the
+    /// door.
+    fn reinstall_with<F: FnOnce()>(
+        &self,
+        old_path: &Path,
+        new_path: &Path,
+        between: F,
+    ) -> Result<(), Error> {
+        self.install(new_path)?;
+        between();
+        uninstall(old_path).map_err(Error::DetachDoor)
+    }
+
+    /// Install this door at each of several paths, all or nothing.
+    ///
+    /// This is handy for compatibility aliases, or for exposing a door in both
+    /// a private and a public location. Each path gets its own clone of the
+    /// door (see [`Door::try_clone`]), and the clones are returned in the same
+    /// order as `paths`. If any install fails, every path installed so far is
+    /// detached and removed again before the error is returned, leaving the
+    /// filesystem as it was and this door unrevoked.
+    ///
+    /// Remember that dropping *any* of the returned clones revokes the door for
+    /// all of them, this one included.
+    pub fn install_at_many<P: AsRef<Path>>(
+        &self,
+        paths: &[P],
+    ) -> Result<Vec<Door>, Error> {
+        let mut clones = Vec::with_capacity(paths.len());
+        for (installed, path) in paths.iter().enumerate() {
+            let result = match self.try_clone() {
+                Ok(clone) => {
+                    let result = clone.install(path);
+                    clones.push(clone);
+                    result
+                }
+                Err(e) => Err(Error::CloneDoor(e)),
+            };
+            if let Err(e) = result {
+                for path in &paths[..installed] {
+                    illumos::fdetach(path).ok();
+                    std::fs::remove_file(path).ok();
+                }
+                // Dropping a clone would revoke the door, this one included,
+                // so close their descriptors without dropping them.
+                for clone in clones {
+                    let clone = ManuallyDrop::new(clone);
+                    unsafe { libc::close(clone.0) };
+                }
+                return Err(e);
+            }
+        }
+        Ok(clones)
+    }
+}
+
+/// A fluent way to create a [`Door`]; see [`Door::builder`].
+///
+/// Every option starts out the way [`Door::create`] would have it: no cookie,
+/// no [`DoorAttributes`], no private thread pool, and ordinary door threads.
+#[derive(Debug, Default, Clone)]
+pub struct DoorBuilder {
+    cookie: u64,
+    attrs: DoorAttributes,
+    pool_size: Option<usize>,
+    threads: illumos::DoorThreads,
+}
+
+impl DoorBuilder {
+    /// The cookie the kernel will hand the server procedure on every call.
+    pub fn cookie(mut self, cookie: u64) -> Self {
+        self.cookie = cookie;
+        self
+    }
+
+    /// The [`DoorAttributes`] to create the door with. These replace any set
+    /// earlier, but [`DoorBuilder::private_pool`] will still add
+    /// [`DoorAttributes::private`].
+    pub fn attributes(mut self, attrs: DoorAttributes) -> Self {
+        self.attrs = attrs;
+        self
+    }
+
+    /// Serve the door from a private pool of `pool_size` threads, as with
+    /// [`Door::create_private`].
+    pub fn private_pool(mut self, pool_size: usize) -> Self {
+        self.pool_size = Some(pool_size);
+        self
+    }
+
+    /// Give each of the door's server threads a stack of `bytes` bytes.
+    ///
+    /// A request's data is copied onto the stack of the thread which answers
+    /// it, so the stack size bounds how large a request the door can take:
+    /// past that, clients get [`DoorCallError::E2BIG`][crate::DoorCallError].
+    /// Raising it lets the door accept larger requests, at the price of that
+    /// much address space for every server thread, whether or not a large
+    /// request ever arrives.
+    ///
+    /// Like [`DoorBuilder::thread_name`], this has the door served from a
+    /// private pool of its own threads, created with
+    /// [`illumos::door_xcreate`]. [`DoorBuilder::private_pool`] sets how many
+    /// are started up front (one, if not given); more are added whenever they
+    /// are all busy, unless the door has
+    /// [`DoorAttributes::no_depletion_callback`].
+    pub fn stack_size(mut self, bytes: usize) -> Self {
+        self.threads.stack_size = Some(bytes);
+        self
+    }
+
+    /// Name each of the door's server threads `name`, so that they stand out
+    /// in `pstack` and `mdb`.
+    ///
+    /// illumos keeps at most 31 bytes of a thread's name
+    /// ([`PTHREAD_MAX_NAMELEN_NP`][illumos::pthread_h::PTHREAD_MAX_NAMELEN_NP],
+    /// less the terminating NUL), so a longer name is cut short. The threads
+    /// come from a private pool, as with [`DoorBuilder::stack_size`].
+    pub fn thread_name(mut self, name: &str) -> Self {
+        self.threads.name = Some(name.to_string());
+        self
+    }
+
+    /// Decide, each time the door's threads are all busy, whether to add one.
+    ///
+    /// illumos notices when a call arrives at a private pool with no thread
+    /// free to answer it, and asks for another thread: this is the depletion
+    /// callback. Here it goes to `f`, which returns `true` to have a thread
+    /// added, or `false` to let the call wait for a busy one, say to keep the
+    /// pool below some size. Not having `f` is the same as always returning
+    /// `true`.
+    ///
+    /// Depletion callbacks only exist for private pools, so as with
+    /// [`DoorBuilder::stack_size`], the door is served from a pool of its own
+    /// threads; an ordinary door, or one created with just
+    /// [`DoorAttributes::private`], calls the process-wide
+    /// `door_server_create` function instead. With
+    /// [`DoorAttributes::no_depletion_callback`], there are no callbacks, so
+    /// `f` is never called and the pool never grows.
+    pub fn on_depletion<F>(mut self, f: F) -> Self
+    where
+        F: Fn() -> bool + Send + Sync + 'static,
+    {
+        self.threads.on_depletion = Some(Arc::new(f));
+        self
+    }
+
+    /// Create the door, with `sp` as its server procedure.  This will not
+    /// expose the door to the filesystem.
+    pub fn build(self, sp: illumos::ServerProcedure) -> Result<Door, Error> {
+        let mut attrs = self.attrs;
+        let threads = &self.threads;
+        let xcreate = threads.stack_size.is_some()
+            || threads.name.is_some()
+            || threads.on_depletion.is_some();
+        if self.pool_size.is_some() || xcreate {
+            attrs.insert(DoorAttributes::private());
+        }
+        let door = match xcreate {
+            true => {
+                let nthread = self.pool_size.unwrap_or(1);
+                Door::xcreate(sp, self.cookie, attrs, self.threads, nthread)?
+            }
+            false => match illumos::door_create(sp, self.cookie, attrs) {
+                Ok(fd) => Door::adopt(fd)?,
+                Err(e) => return Err(Error::CreateDoor(e)),
+            },
+        };
+        #[cfg(all(feature = "abi-check", debug_assertions))]
+        if !attrs.contains(DoorAttributes::private()) {
+            door.smoke_test();
+        }
+        match self.pool_size {
+            Some(pool_size) if !xcreate => door.bind_pool(pool_size),
+            _ => Ok(door),
+        }
+    }
+}
+
+impl AsRawFd for Door {
+    fn as_raw_fd(&self) -> RawFd {
+        self.0
+    }
+}
+
+/// Give up the door's descriptor without revoking the door.
+///
+/// A `Door` revokes its door when dropped; the [`OwnedFd`] this produces
+/// merely closes its descriptor. The door stays alive for as long as anyone
+/// holds a descriptor for it, or it is attached to the filesystem. Any state
+/// shared with [`Door::create_with_arc`], like the recipe for a door's threads,
+/// is never given up, since the door may go on being called.
+impl From<Door> for OwnedFd {
+    fn from(door: Door) -> Self {
+        let door = ManuallyDrop::new(door);
+        unsafe { OwnedFd::from_raw_fd(door.0) }
     }
 }
 
@@ -126,6 +766,245 @@ impl Drop for Door {
         unsafe {
             illumos::door_h::door_revoke(self.0);
         }
+        if let Some(state) = self.1 {
+            state.release();
+        }
+    }
+}
+
+/// A server procedure which expects its cookie to point to an `S`
+///
+/// These are generated by `#[server_procedure(state = S)]`, and can only be
+/// turned into a door by [`Door::create_with_state`].
+pub struct StatefulProcedure<S> {
+    sp: illumos::ServerProcedure,
+    _state: PhantomData<fn(&S)>,
+}
+
+impl<S> StatefulProcedure<S> {
+    /// Pin a raw server procedure to a state type.
+    ///
+    /// # Safety
+    ///
+    /// `sp` must treat its cookie as a `*const S`, and nothing else.
+    pub const unsafe fn new(sp: illumos::ServerProcedure) -> Self {
+        Self {
+            sp,
+            _state: PhantomData,
+        }
+    }
+}
+
+impl<S> Clone for StatefulProcedure<S> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<S> Copy for StatefulProcedure<S> {}
+
+/// The cookie of a door created with [`Door::create_with_arc`]
+///
+/// A door can be revoked while some of its calls are still in progress, and
+/// those calls still need their cookie to point somewhere. So the cookie does
+/// not point at the `Arc`'d state itself, but at this small slot, which holds
+/// the door's reference to it. The slot lives forever; the state does not.
+/// When the door is dropped, the slot is emptied, and calls which start after
+/// that find nothing in it. Calls which already took their own reference keep
+/// the state alive until they are done with it.
+pub struct ArcState<T>(Mutex<Option<Arc<T>>>);
+
+impl<T> ArcState<T> {
+    /// Take a reference to the state, unless the door has been dropped.
+    pub fn get(&self) -> Option<Arc<T>> {
+        self.0.lock().unwrap().clone()
+    }
+}
+
+/// Door state in memory shared with this process's children
+///
+/// [`Shared::new`] maps an anonymous `MAP_SHARED` region and moves a value
+/// into it. Because the mapping is shared rather than copied on `fork`, every
+/// process forked afterwards sees the very same value, at the very same
+/// address. Create a door over it with [`Door::create_with_state`] and
+/// `#[server_procedure(state = Shared<T>)]`, and handlers find it with
+/// [`Request::shared_state`].
+///
+/// A door is always served by the process which created it, even once its
+/// descriptor has been inherited. What the shared region buys is that other
+/// processes see the state that door maintains -- they can read or update it
+/// directly, or serve doors of their own over it.
+///
+/// Nothing synchronizes the region for you. Several threads of several
+/// processes may touch it at once, so `T` must do its own synchronization,
+/// with atomics or process-shared locks. See [`Shared::new`] for what else `T`
+/// must be careful of. The region is never unmapped, and the value is never
+/// dropped.
+pub struct Shared<T>(T);
+
+impl<T: Sync + 'static> Shared<T> {
+    /// Move `value` into a newly mapped shared region.
+    ///
+    /// The region is aligned to a page, so a `T` which needs stricter
+    /// alignment than that is refused with [`io::ErrorKind::InvalidInput`].
+    ///
+    /// # Safety
+    ///
+    /// Every process which inherits the region may read `value`, so it must
+    /// not hold pointers into any one process's memory, which would mean
+    /// nothing to the others: no `Box`, `Vec`, `String`, or
+    /// `std::sync::Mutex`, and nothing which holds one. Atomics and plain
+    /// data, like integers and arrays of them, are fine.
+    pub unsafe fn new(value: T) -> io::Result<&'static Self> {
+        let page = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+        if std::mem::align_of::<Self>() > page as usize {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "shared state needs more than page alignment",
+            ));
+        }
+        let len = std::mem::size_of::<Self>().max(1);
+        let region = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED | libc::MAP_ANON,
+                -1,
+                0,
+            )
+        };
+        if region == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        let shared = region as *mut Self;
+        unsafe {
+            shared.write(Self(value));
+            Ok(&*shared)
+        }
+    }
+}
+
+impl<T> std::ops::Deref for Shared<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+/// Whether a door is open for business, for [`Door::suspend`]
+///
+/// Gates start out open. Keep one in a door's state, and have its handlers
+/// turn calls away while it is closed:
+///
+/// ```
+/// use doors::server::{Gate, Request, Response};
+///
+/// #[doors::server_procedure(state = Gate)]
+/// fn serve(x: Request<'_, Gate>) -> Response<Vec<u8>> {
+///     if !x.state.is_open() {
+///         return Response::unavailable();
+///     }
+///     Response::new(x.data.to_vec())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Gate(AtomicBool);
+
+impl Gate {
+    /// A new, open gate.
+    pub const fn new() -> Self {
+        Self(AtomicBool::new(true))
+    }
+
+    /// Whether calls should be served.
+    pub fn is_open(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+impl Default for Gate {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A request to stop serving, for [`Door::wait_for_shutdown`]
+///
+/// Keep one in a `static`, or in a door's state, and have a handler call
+/// [`Shutdown::request`] when it recognizes a "shut down" call. Any other
+/// thread can request a shutdown the same way.
+///
+/// ```
+/// use doors::server::{Request, Response, Shutdown};
+///
+/// static SHUTDOWN: Shutdown = Shutdown::new();
+///
+/// #[doors::server_procedure]
+/// fn serve(x: Request<'_>) -> Response<&[u8]> {
+///     if x.data == b"shutdown" {
+///         SHUTDOWN.request();
+///     }
+///     Response::new(x.data)
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Shutdown {
+    requested: Mutex<bool>,
+    wake: Condvar,
+}
+
+impl Shutdown {
+    /// A shutdown which has not been requested yet.
+    pub const fn new() -> Self {
+        Self {
+            requested: Mutex::new(false),
+            wake: Condvar::new(),
+        }
+    }
+
+    /// Ask whoever is waiting in [`Door::wait_for_shutdown`] to stop.
+    pub fn request(&self) {
+        *self.requested.lock().unwrap() = true;
+        self.wake.notify_all();
+    }
+
+    /// Whether a shutdown has been requested.
+    pub fn is_requested(&self) -> bool {
+        *self.requested.lock().unwrap()
+    }
+
+    /// Block until a shutdown is requested.
+    fn wait(&self) {
+        let requested = self.requested.lock().unwrap();
+        drop(self.wake.wait_while(requested, |requested| !*requested));
+    }
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Gives up what a door holds on to, its state or the recipe for its threads,
+/// when the door is dropped.
+trait Release: Sync {
+    fn release(&self);
+}
+
+impl Release for illumos::DoorThreadsSlot {
+    fn release(&self) {
+        self.clear();
+    }
+}
+
+impl<T: Send + Sync> Release for ArcState<T> {
+    fn release(&self) {
+        // Drop the state outside of the lock, in case its destructor is slow.
+        let state = self.0.lock().unwrap().take();
+        drop(state);
     }
 }
 
@@ -133,91 +1012,1776 @@ impl Drop for Door {
 ///
 /// This type allows us to write server procedures that accept a single argument
 /// rather than five separate arguments.
-#[derive(Copy, Clone)]
-pub struct Request<'a> {
+///
+/// Both `data` and `descriptors` live in the door thread's stack area, so they
+/// are only valid for the duration of the invocation. A handler which wants to
+/// hold on to one of the client's descriptors beyond that should take its own
+/// copy with [`Request::dup_descriptor`].
+///
+/// Server procedures declared with `#[server_procedure(state = S)]` receive a
+/// `Request<'_, S>`, whose `state` refers to the value given to
+/// [`Door::create_with_state`]. For all others, `state` is just `()`.
+pub struct Request<'a, S = ()> {
     pub cookie: u64,
     pub data: &'a [u8],
     pub descriptors: &'a [door_desc_t],
+    pub state: &'a S,
 }
 
-/// Server-Side representation of the client's door results
-///
-/// This type can refer to either memory on the stack (which will be cleaned up
-/// automatically when door_return is called) or memory on the heap (which
-/// will not). If you return an object that refers to memory on the heap, it is
-/// your responsibility to free it later.
-///
-/// Many door servers allocate a per-thread response area so that each thread
-/// can re-use this area for every door invocation assigned to it. That way the
-/// memory leaked is constant. Typically, applications that take this approach
-/// will free these per-thread response areas when the DOOR_UNREF message is
-/// sent.
-pub struct Response<C: AsRef<[u8]>> {
-    pub data: Option<C>,
-    pub num_descriptors: u32,
-    pub descriptors: [DoorFd; 2],
+impl<'a, S> Clone for Request<'a, S> {
+    fn clone(&self) -> Self {
+        *self
+    }
 }
 
-impl<C: AsRef<[u8]>> Response<C> {
-    pub fn new(data: C) -> Self {
-        let descriptors = [DoorFd::new(-1, true), DoorFd::new(-1, true)];
-        let num_descriptors = 0;
-        Self {
-            data: Some(data),
-            descriptors,
-            num_descriptors,
-        }
+impl<'a, S> Copy for Request<'a, S> {}
+
+impl<'a, T> Request<'a, ArcState<T>> {
+    /// Take a reference to the state shared with [`Door::create_with_arc`].
+    ///
+    /// This is `None` if the door has already been dropped. Hold on to the
+    /// reference for as long as the handler needs the state.
+    pub fn arc_state(&self) -> Option<Arc<T>> {
+        self.state.get()
     }
+}
 
-    pub fn empty() -> Self {
-        let data = None;
-        let descriptors = [DoorFd::new(-1, true), DoorFd::new(-1, true)];
-        let num_descriptors = 0;
-        Self {
-            data,
-            descriptors,
-            num_descriptors,
-        }
+impl<'a, T> Request<'a, Shared<T>> {
+    /// The state this door shares with other processes. See [`Shared`].
+    pub fn shared_state(&self) -> &'a T {
+        &self.state.0
     }
+}
 
-    pub fn add_descriptor(mut self, fd: RawFd, release: bool) -> Self {
-        if self.num_descriptors == 2 {
-            panic!("Only 2 descriptors are supported")
+impl<'a, S> Request<'a, S> {
+    /// Whether this is an unreferenced notification rather than a call.
+    ///
+    /// Doors created with [`DoorAttributes::unref`] or
+    /// [`DoorAttributes::unref_multi`] have their server procedure invoked,
+    /// with no data and no descriptors, once the door has no clients left.
+    /// Nobody is waiting on the response to a notification. A `DOOR_UNREF`
+    /// door is notified at most once; a `DOOR_UNREF_MULTI` door each time its
+    /// last client goes away, so a handler should be ready to see this more
+    /// than once. Handlers generated by [`server_procedure`] are only passed
+    /// notifications if they ask for them, with `unref`.
+    ///
+    /// [`server_procedure`]: crate::server_procedure
+    pub fn is_unref(&self) -> bool {
+        self.data.as_ptr() == illumos::door_h::DOOR_UNREF_DATA as *const u8
+    }
+
+    /// The request's data as an array of exactly `N` bytes.
+    ///
+    /// This is `None` if the client sent any other amount, so a handler which
+    /// expects, say, a 16-byte key can refuse anything else rather than
+    /// indexing past the end of a short request and panicking.
+    pub fn data_array<const N: usize>(&self) -> Option<&'a [u8; N]> {
+        self.data.try_into().ok()
+    }
+
+    /// The priority the client tagged this request with, and the request
+    /// itself.
+    ///
+    /// This is `None` unless the client called
+    /// [`Client::call_with_priority`][crate::Client::call_with_priority]. The
+    /// priority is only a hint, higher meaning more urgent; what to do with
+    /// it, if anything, is up to the server.
+    pub fn priority(&self) -> Option<(u8, &'a [u8])> {
+        let frame = self.data.strip_prefix(&crate::PRIORITY_FRAME_MAGIC[..])?;
+        frame
+            .split_first()
+            .map(|(&priority, data)| (priority, data))
+    }
+
+    /// Answer this request with `data`, right here.
+    ///
+    /// This is for handlers written in an imperative style, which would
+    /// rather reply from the middle of their logic than return a [`Response`].
+    /// It calls [`door_return`], so it is terminal: nothing after it runs, and
+    /// nothing still alive in the handler is dropped. It must be reached
+    /// exactly once per request, on the thread which received it. `data` is
+    /// copied out by the kernel, so it may borrow from the handler's locals,
+    /// but anything which owns memory will leak. Any [`Invocation`] this
+    /// thread has entered is closed first, just as [`Invocation::exit`] would.
+    pub fn reply(&self, data: &[u8]) -> ! {
+        close_invocation();
+        door_return(data, &[])
+    }
+
+    /// Answer this request with `data` and `descriptors`.
+    ///
+    /// Like [`Request::reply`], this never returns. Descriptors marked for
+    /// release are closed in this process once the kernel has passed them on.
+    pub fn reply_with(&self, data: &[u8], descriptors: &[DoorFd]) -> ! {
+        close_invocation();
+        door_return(data, descriptors)
+    }
+
+    /// Turn this request away with an error frame carrying `code`, right
+    /// here.
+    ///
+    /// This is the failure path for imperative handlers, in place of an
+    /// `unwrap` which panics: the client decodes the frame with
+    /// [`DoorArgument::as_result`][crate::DoorArgument::as_result], just as if
+    /// the handler had returned [`Response::err_bytes`] with no detail. Like
+    /// [`Request::reply`], this never returns.
+    pub fn reply_error(&self, code: u32) -> ! {
+        close_invocation();
+        Response::err_bytes(code, &[]).door_return()
+    }
+
+    /// Credentials of the client which made this request.
+    ///
+    /// See [`illumos::Ucred`] for what they contain. This must be called from
+    /// the thread serving the request, which is where a `Request` is normally
+    /// found anyway.
+    pub fn ucred(&self) -> Result<illumos::Ucred, illumos::Error> {
+        illumos::door_ucred()
+    }
+
+    /// Collect one chunk of a payload sent with
+    /// [`Client::call_chunked`][crate::Client::call_chunked].
+    ///
+    /// The chunk is added to the partial payload kept in `accumulator` for its
+    /// transfer. Once the last chunk arrives, the whole payload is handed back
+    /// and forgotten by the accumulator, and the handler should answer the
+    /// request as it would any other; until then, nobody is interested in the
+    /// responses. Several transfers can be in flight at once, from any number
+    /// of clients, since each carries its own identifier.
+    ///
+    /// Requests which are not chunk frames, or which disagree with what the
+    /// accumulator already knows about their transfer, are
+    /// [`Accumulated::Invalid`].
+    pub fn accumulate(&self, accumulator: &Accumulator) -> Accumulated {
+        let header = match self.data.strip_prefix(&crate::CHUNK_FRAME_MAGIC[..])
+        {
+            Some(rest) if rest.len() >= 24 => rest,
+            _ => return Accumulated::Invalid,
+        };
+        let word = |i: usize| {
+            let bytes = header[i * 8..(i + 1) * 8].try_into().unwrap();
+            u64::from_le_bytes(bytes)
+        };
+        let (id, offset, total) = (word(0), word(1), word(2));
+        let chunk = &header[24..];
+
+        let mut transfers = accumulator.transfers.lock().unwrap();
+        let payload = transfers.entry(id).or_default();
+        let end = offset.checked_add(chunk.len() as u64);
+        if offset != payload.len() as u64 || end.is_none_or(|e| e > total) {
+            transfers.remove(&id);
+            return Accumulated::Invalid;
+        }
+        payload.extend_from_slice(chunk);
+        match payload.len() as u64 == total {
+            true => Accumulated::Complete(transfers.remove(&id).unwrap()),
+            false => Accumulated::Partial,
         }
+    }
 
-        let desc = DoorFd::new(fd, release);
-        self.descriptors[self.num_descriptors as usize] = desc;
-        self.num_descriptors += 1;
+    /// Duplicate one of the client's descriptors.
+    ///
+    /// The resulting [`OwnedFd`] is independent of this invocation, so the
+    /// handler may stash it and keep using it on later calls. It is created
+    /// with `FD_CLOEXEC` set.
+    pub fn dup_descriptor(&self, index: usize) -> io::Result<OwnedFd> {
+        let desc = match self.descriptors.get(index) {
+            Some(desc) => desc,
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "no descriptor at that index",
+                ))
+            }
+        };
+        let d_desc = unsafe { desc.d_data.d_desc };
+        let fd = unsafe { BorrowedFd::borrow_raw(d_desc.d_descriptor) };
+        fd.try_clone_to_owned()
+    }
 
-        self
+    /// Clients for the doors the client sent along with this request.
+    ///
+    /// A client can pass one of its own doors as a callback, for the server to
+    /// call later on. Each descriptor is duplicated, as with
+    /// [`Request::dup_descriptor`], so the resulting [`Client`]s own their
+    /// descriptors and may outlive this invocation. Descriptors which do not
+    /// refer to doors yield [`io::ErrorKind::InvalidInput`].
+    pub fn incoming_doors(
+        &self,
+    ) -> impl Iterator<Item = io::Result<Client>> + '_ {
+        (0..self.descriptors.len()).map(|index| {
+            let fd = self.dup_descriptor(index)?;
+            Client::from_owned_fd(fd).map_err(|(_, e)| e)
+        })
     }
 }
 
-fn create_new_file<P: AsRef<Path>>(path: P) -> io::Result<File> {
-    File::options()
-        .read(true)
-        .write(true)
-        .create_new(true)
-        .open(path)
+/// Partial payloads of chunked transfers, for [`Request::accumulate`]
+///
+/// Keep one of these in a door's state. Transfers whose clients gave up part
+/// way through stay here until [`Accumulator::clear`] is called.
+#[derive(Default)]
+pub struct Accumulator {
+    transfers: Mutex<HashMap<u64, Vec<u8>>>,
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+impl Accumulator {
+    /// How many transfers are partway done.
+    pub fn in_progress(&self) -> usize {
+        self.transfers.lock().unwrap().len()
+    }
 
-    #[test]
-    #[should_panic]
-    fn create_new_fails_if_file_exists() {
-        match File::create("/tmp/create_new_fail.txt") {
-            // If we can't create the "original" file, we want the test to fail,
-            // which means that we *don't* want to panic.
-            Err(e) => {
-                eprintln!("{:?}", e);
-                assert!(true)
-            }
+    /// Forget every partial payload.
+    pub fn clear(&self) {
+        self.transfers.lock().unwrap().clear()
+    }
+}
+
+/// What became of a chunk given to [`Request::accumulate`]
+#[derive(Debug, PartialEq, Eq)]
+pub enum Accumulated {
+    /// More chunks are needed before the payload is complete.
+    Partial,
+
+    /// This was the last chunk, and here is the whole payload.
+    Complete(Vec<u8>),
+
+    /// The request was not a well-formed chunk of a transfer. Any partial
+    /// payload it claimed to belong to has been dropped.
+    Invalid,
+}
+
+/// Server-Side representation of the client's door results
+///
+/// This type can refer to memory on the stack, to the client's request, or to
+/// memory on the heap. Since `door_return` never returns, nothing that the
+/// response owns can be freed while it is being returned. Many door servers in C
+/// allocate a per-thread response area so that each thread can re-use this area
+/// for every door invocation assigned to it, and the memory leaked is constant.
+///
+/// This is handled for you: each door thread holds on to the data and the
+/// descriptor list from its previous invocation, and drops them when it returns
+/// the next one. So a handler can return a `Vec<u8>`, or a [`Cow<[u8]>`][Cow]
+/// which either borrows from the request (for zero-copy replies) or owns fresh
+/// data, without leaking. Data which does not need dropping, like an array or a
+/// slice, is returned where it lies.
+///
+/// A deferred drop happens after the invocation it belonged to is over, so the
+/// data's destructor must not rely on anything it borrowed from that
+/// invocation. Every standard library container, `Cow` included, is fine.
+///
+/// [Cow]: std::borrow::Cow
+pub struct Response<C: AsRef<[u8]>> {
+    pub data: Option<C>,
+    pub descriptors: Vec<DoorFd>,
+}
+
+thread_local! {
+    /// Descriptor list passed to this thread's most recent `door_return`.
+    static RETURNED_DESCRIPTORS: RefCell<Vec<DoorFd>> = const {
+        RefCell::new(Vec::new())
+    };
+
+    /// Data passed to this thread's most recent `door_return`, if it needs to
+    /// be dropped.
+    static RETURNED_DATA: RefCell<Option<ReturnedData>> = const {
+        RefCell::new(None)
+    };
+}
+
+/// Response data whose type has been forgotten, but not how to drop it.
+struct ReturnedData {
+    data: *mut (),
+    drop: unsafe fn(*mut ()),
+}
+
+impl ReturnedData {
+    /// Move `data` to the heap, and return it along with its bytes.
+    fn new<C: AsRef<[u8]>>(data: C) -> (Self, *const u8, usize) {
+        unsafe fn drop_boxed<C>(data: *mut ()) {
+            drop(Box::from_raw(data as *mut C));
+        }
+        let data = Box::into_raw(Box::new(data));
+        let bytes = unsafe { (*data).as_ref() };
+        let returned = Self {
+            data: data as *mut (),
+            drop: drop_boxed::<C>,
+        };
+        (returned, bytes.as_ptr(), bytes.len())
+    }
+}
+
+impl Drop for ReturnedData {
+    fn drop(&mut self) {
+        unsafe { (self.drop)(self.data) }
+    }
+}
+
+impl<C: AsRef<[u8]>> Response<C> {
+    pub fn new(data: C) -> Self {
+        Self {
+            data: Some(data),
+            descriptors: Vec::new(),
+        }
+    }
+
+    pub fn empty() -> Self {
+        Self {
+            data: None,
+            descriptors: Vec::new(),
+        }
+    }
+
+    /// Build a response carrying several descriptors at once.
+    ///
+    /// Each descriptor is paired with its `release` flag, which has the same
+    /// meaning as in [`DoorFd::new`].
+    pub fn with_descriptors(data: C, fds: &[(RawFd, bool)]) -> Self {
+        let descriptors = fds
+            .iter()
+            .map(|&(fd, release)| DoorFd::new(fd, release))
+            .collect();
+        Self {
+            data: Some(data),
+            descriptors,
+        }
+    }
+
+    /// Transform this response's data, keeping its descriptors as they are.
+    ///
+    /// A response without data stays that way, and `f` is not called.
+    ///
+    /// ```
+    /// use doors::server::Response;
+    ///
+    /// let framed = Response::new(*b"hi").map(|data| {
+    ///     let mut frame = vec![data.len() as u8];
+    ///     frame.extend_from_slice(&data);
+    ///     frame
+    /// });
+    /// assert_eq!(framed.data, Some(vec![2, b'h', b'i']));
+    /// ```
+    pub fn map<D, F>(self, f: F) -> Response<D>
+    where
+        D: AsRef<[u8]>,
+        F: FnOnce(C) -> D,
+    {
+        Response {
+            data: self.data.map(f),
+            descriptors: self.descriptors,
+        }
+    }
+
+    /// Build a response whose descriptors come from an iterator.
+    ///
+    /// The descriptors must stay open until the response has been returned.
+    /// Those marked to be released are closed by the kernel once the client
+    /// has its copy.
+    pub fn from_descriptors<I>(data: C, descriptors: I) -> Self
+    where
+        I: IntoIterator<Item = DoorFd>,
+    {
+        Self {
+            data: Some(data),
+            descriptors: descriptors.into_iter().collect(),
+        }
+    }
+
+    /// Build a response which carries descriptors and no data.
+    ///
+    /// This is the usual answer from a server which opens files on its
+    /// clients' behalf. [`Response::add_descriptor`] can still add more.
+    ///
+    /// ```no_run
+    /// use doors::illumos::DoorFd;
+    /// use doors::server::Response;
+    /// use std::fs::File;
+    ///
+    /// let logs = ["/var/log/a", "/var/log/b"]
+    ///     .map(|path| DoorFd::from_owned(File::open(path).unwrap().into()));
+    /// let response = Response::<[u8; 0]>::descriptors_only(logs);
+    /// ```
+    pub fn descriptors_only<I>(descriptors: I) -> Self
+    where
+        I: IntoIterator<Item = DoorFd>,
+    {
+        Self {
+            data: None,
+            descriptors: descriptors.into_iter().collect(),
+        }
+    }
+
+    pub fn add_descriptor(mut self, fd: RawFd, release: bool) -> Self {
+        self.descriptors.push(DoorFd::new(fd, release));
+        self
+    }
+
+    /// Send the caller a handle to another door this server manages.
+    ///
+    /// The caller receives a descriptor for `door`, which it can turn into a
+    /// [`Client`] and call like any other. This is how a door "directory" -- a
+    /// door which hands out other doors -- is built.
+    ///
+    /// Since `door` is only borrowed, its own descriptor is never released: it
+    /// must stay open for `door` to be revoked later. If `release` is set, a
+    /// duplicate of the descriptor is sent and released instead. Should that
+    /// duplicate be impossible to make, `door`'s own descriptor is sent
+    /// without releasing it, and the caller receives a working handle all the
+    /// same.
+    pub fn add_door_client(self, door: &Door, release: bool) -> Self {
+        if release {
+            let fd = unsafe { BorrowedFd::borrow_raw(door.0) };
+            if let Ok(fd) = fd.try_clone_to_owned() {
+                return self.add_descriptor(fd.into_raw_fd(), true);
+            }
+        }
+        self.add_descriptor(door.0, false)
+    }
+
+    /// Hand this response back to the client.
+    ///
+    /// This calls [`door_return`][illumos::door_h::door_return], so like that
+    /// function it never returns. The [`server_procedure`] macro calls this for
+    /// you. Inside an [`Invocation`], close it with [`Invocation::exit`]
+    /// first.
+    ///
+    /// [`server_procedure`]: crate::server_procedure
+    pub fn door_return(self) -> ! {
+        let descriptors = self.descriptors;
+        let desc_ptr = descriptors.as_ptr();
+        let num_desc = descriptors.len();
+
+        // Moving the Vec does not move its contents, so desc_ptr stays valid.
+        // Stashing it here frees the previous invocation's list rather than
+        // leaking one list per call.
+        RETURNED_DESCRIPTORS.with(|slot| slot.replace(descriptors));
+
+        // As with the descriptors, anything which needs dropping is stashed
+        // until the next invocation. Anything else stays right where it is.
+        let data = self.data;
+        let (returned, data_ptr, data_size) = match &data {
+            Some(_) if std::mem::needs_drop::<C>() => {
+                let (returned, ptr, len) = ReturnedData::new(data.unwrap());
+                (Some(returned), ptr, len)
+            }
+            Some(data) => (None, data.as_ref().as_ptr(), data.as_ref().len()),
+            None => (None, [].as_ptr(), 0),
+        };
+        RETURNED_DATA.with(|slot| slot.replace(returned));
+
+        let data = unsafe { std::slice::from_raw_parts(data_ptr, data_size) };
+        let descriptors =
+            unsafe { std::slice::from_raw_parts(desc_ptr, num_desc) };
+        door_return(data, descriptors)
+    }
+}
+
+/// Collect descriptors into a response which carries no data.
+impl<C: AsRef<[u8]>> FromIterator<DoorFd> for Response<C> {
+    fn from_iter<I: IntoIterator<Item = DoorFd>>(iter: I) -> Self {
+        Self {
+            data: None,
+            descriptors: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl<C: AsRef<[u8]>> Extend<DoorFd> for Response<C> {
+    fn extend<I: IntoIterator<Item = DoorFd>>(&mut self, iter: I) {
+        self.descriptors.extend(iter)
+    }
+}
+
+impl<'a> Response<&'a [u8]> {
+    /// Answer with bytes that belong to the door's state.
+    ///
+    /// This is for doors whose answer is mostly a view of their state, like
+    /// a configuration server handing out a document it prepared ahead of
+    /// time. The state outlives every invocation, so the bytes can be returned
+    /// where they lie, without copying them or resorting to a `static mut`
+    /// buffer.
+    ///
+    /// ```no_run
+    /// use doors::server::{Door, Request, Response};
+    ///
+    /// struct Config {
+    ///     rendered: Vec<u8>,
+    /// }
+    ///
+    /// #[doors::server_procedure(state = Config)]
+    /// fn config(x: Request<'_, Config>) -> Response<&[u8]> {
+    ///     Response::from_state(&x, |state| &state.rendered)
+    /// }
+    ///
+    /// let state = Box::leak(Box::new(Config { rendered: b"a=1".to_vec() }));
+    /// let door = Door::create_with_state(config, state).unwrap();
+    /// ```
+    pub fn from_state<S, F>(request: &Request<'a, S>, f: F) -> Self
+    where
+        F: FnOnce(&'a S) -> &'a [u8],
+    {
+        Self::new(f(request.state))
+    }
+}
+
+impl Response<Vec<u8>> {
+    /// Build a response by writing it out.
+    ///
+    /// `f` is given a writer, and whatever it writes becomes the response, so
+    /// handlers can use `write!` or a serializer rather than assembling a
+    /// `Vec` by hand. The bytes are owned by the response, and freed once this
+    /// door thread returns its next response, like any other `Vec` (see
+    /// [`Response`]). Any error from `f` is passed along.
+    ///
+    /// ```
+    /// use doors::server::{Request, Response};
+    ///
+    /// #[doors::server_procedure]
+    /// fn describe(x: Request<'_>) -> Response<Vec<u8>> {
+    ///     Response::build(|w| write!(w, "{} bytes", x.data.len()))
+    ///         .unwrap_or_else(|_| Response::err_bytes(1, b"cannot describe"))
+    /// }
+    /// ```
+    pub fn build<F>(f: F) -> io::Result<Self>
+    where
+        F: FnOnce(&mut dyn Write) -> io::Result<()>,
+    {
+        let mut data = Vec::new();
+        f(&mut data)?;
+        Ok(Self::new(data))
+    }
+
+    /// Build a response from the contents of a file.
+    ///
+    /// Reads from the file's current position to its end, into a buffer owned
+    /// by the response (see [`Response::build`]). A file with more than `max`
+    /// bytes left in it is refused with [`io::ErrorKind::InvalidData`], after
+    /// reading at most one byte past the limit, so a handler never allocates
+    /// more than it asked for.
+    pub fn from_file(file: &mut File, max: usize) -> io::Result<Self> {
+        let mut data = Vec::new();
+        file.take((max as u64).saturating_add(1))
+            .read_to_end(&mut data)?;
+        if data.len() > max {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("file holds more than {} bytes", max),
+            ));
+        }
+        Ok(Self::new(data))
+    }
+
+    /// Answer with `data`, and report that `consumed` bytes of the request
+    /// were processed.
+    ///
+    /// This is for protocols in which a client sends as much framed input as
+    /// it has, and the server handles only what it can, say only the complete
+    /// frames. The client reads the count back with
+    /// [`DoorArgument::consumed`][crate::DoorArgument::consumed]. See
+    /// [`CONSUMED_FRAME_MAGIC`][crate::CONSUMED_FRAME_MAGIC] for the layout.
+    pub fn with_consumed(data: &[u8], consumed: usize) -> Self {
+        let mut frame = Vec::with_capacity(16 + data.len());
+        frame.extend_from_slice(&crate::CONSUMED_FRAME_MAGIC);
+        frame.extend_from_slice(&(consumed as u64).to_le_bytes());
+        frame.extend_from_slice(data);
+        Self::new(frame)
+    }
+
+    /// Answer with `payload` behind its length, as a little-endian `u32`.
+    ///
+    /// This is the `[len][payload]` framing many protocols use, and which
+    /// [`Client::call_framed`] decodes.
+    ///
+    /// # Panics
+    ///
+    /// If `payload` is longer than `u32::MAX` bytes, which the prefix cannot
+    /// express.
+    pub fn framed(payload: &[u8]) -> Self {
+        let len = u32::try_from(payload.len())
+            .expect("framed payloads must fit in a u32 length");
+        let mut frame = Vec::with_capacity(4 + payload.len());
+        frame.extend_from_slice(&len.to_le_bytes());
+        frame.extend_from_slice(payload);
+        Self::new(frame)
+    }
+
+    /// Build an error frame, for protocols which report errors as data.
+    ///
+    /// A handler which cannot answer a request can send back an error code and
+    /// some detail, rather than panicking (which takes the door thread down
+    /// with it). The client decodes it with
+    /// [`DoorArgument::as_result`][crate::DoorArgument::as_result]. See
+    /// [`ERROR_FRAME_MAGIC`][crate::ERROR_FRAME_MAGIC] for the layout.
+    pub fn err_bytes(code: u32, detail: &[u8]) -> Self {
+        let mut frame = Vec::with_capacity(12 + detail.len());
+        frame.extend_from_slice(&crate::ERROR_FRAME_MAGIC);
+        frame.extend_from_slice(&code.to_le_bytes());
+        frame.extend_from_slice(detail);
+        Self::new(frame)
+    }
+
+    /// Build the error frame which turns a call away while a door is
+    /// suspended. Its code is
+    /// [`DoorError::UNAVAILABLE`][crate::DoorError::UNAVAILABLE].
+    pub fn unavailable() -> Self {
+        Self::err_bytes(crate::DoorError::UNAVAILABLE, b"service unavailable")
+    }
+
+    /// Build the error frame which reports a panic to the client.
+    ///
+    /// `payload` is what [`std::panic::catch_unwind`] caught. The frame's code
+    /// is [`DoorError::SERVER_PANICKED`][crate::DoorError::SERVER_PANICKED],
+    /// and its detail is the panic message, if it had one.
+    pub fn from_panic(payload: Box<dyn Any + Send>) -> Self {
+        let message = match payload.downcast_ref::<&str>() {
+            Some(message) => message.as_bytes(),
+            None => match payload.downcast_ref::<String>() {
+                Some(message) => message.as_bytes(),
+                None => &[],
+            },
+        };
+        Self::err_bytes(crate::DoorError::SERVER_PANICKED, message)
+    }
+}
+
+impl Response<[u8; 1]> {
+    /// Answer with a single byte. See [`Response::scalar`].
+    pub fn scalar_u8(n: u8) -> Self {
+        Self::new([n])
+    }
+}
+
+impl Response<[u8; 4]> {
+    /// Answer with a single number.
+    ///
+    /// The number is sent in little-endian byte order, which is what
+    /// [`DoorArgument::as_u32`][crate::DoorArgument::as_u32] expects on the
+    /// other end. Both ends of a door are always on the same machine, but
+    /// fixing the byte order keeps the protocol honest should the bytes ever be
+    /// stored or forwarded somewhere else.
+    pub fn scalar(n: u32) -> Self {
+        Self::new(n.to_le_bytes())
+    }
+}
+
+impl Response<[u8; 8]> {
+    /// Answer with a single 64-bit number. See [`Response::scalar`].
+    pub fn scalar_u64(n: u64) -> Self {
+        Self::new(n.to_le_bytes())
+    }
+}
+
+/// Send `data` and `descriptors` back to the client, and wait for the next
+/// invocation.
+///
+/// This is [`door_return`][illumos::door_h::door_return] with the pointer and
+/// length conversions done for you, for hand-written `extern "C"` server
+/// procedures. Like `door_return`, it never returns. The kernel copies `data`
+/// and `descriptors` out before this thread moves on, but nothing in them is
+/// ever dropped, so data which owns memory will leak unless it is kept
+/// somewhere which outlives the call. [`Response::door_return`] takes care of
+/// that, and is the better choice where it fits.
+///
+/// A procedure which has entered an [`Invocation`] must close it before
+/// calling this; in debug builds, the process aborts if it has not.
+///
+/// ```
+/// use doors::illumos::door_h::door_desc_t;
+/// use doors::server;
+///
+/// extern "C" fn answer(
+///     _cookie: *const libc::c_void,
+///     _argp: *const libc::c_char,
+///     _arg_size: libc::size_t,
+///     _dp: *const door_desc_t,
+///     _n_desc: libc::c_uint,
+/// ) {
+///     server::door_return(&[42], &[])
+/// }
+/// ```
+pub fn door_return(data: &[u8], descriptors: &[DoorFd]) -> ! {
+    guard_return();
+    unsafe {
+        illumos::door_h::door_return(
+            data.as_ptr() as *const libc::c_char,
+            data.len(),
+            descriptors.as_ptr() as *const door_desc_t,
+            descriptors.len() as libc::c_uint,
+        )
+    };
+
+    // We are only back if the results could not be delivered. Offer the thread
+    // up again with nothing, so the client is not left waiting. Should even
+    // that fail, there is no door left for this thread to serve, and nowhere
+    // to return to, so it sleeps for good.
+    illumos::door_wait();
+    loop {
+        std::thread::park();
+    }
+}
+
+/// How much stack `#[server_procedure(zeroize)]` scrubs by default, in bytes.
+pub const ZEROIZE_DEPTH: usize = 16 * 1024;
+
+/// Wipe what a server procedure may have left behind on its thread's stack.
+///
+/// Door threads are reused: whatever one invocation leaves in its stack area is
+/// still there when the next client's call lands on the same thread. A handler
+/// which deals in secrets -- keys, passwords, session tokens -- can leave
+/// copies of them in its dead stack frames, where a later memory disclosure bug
+/// would find them. This zeroes two regions before the thread goes back to the
+/// pool:
+///
+/// * the `DEPTH` bytes of stack below the caller, where the handler's frames
+///   used to be, and
+/// * the request data at `argp`, unless `response` points into it (as an echo
+///   server's might), since zeroing it then would corrupt the reply.
+///
+/// The response itself cannot be zeroed: the kernel copies it out during
+/// `door_return`, which never returns control to us. Likewise, nothing a
+/// handler copied to the heap is touched. This is what
+/// `#[server_procedure(zeroize)]` calls between the handler and
+/// [`Response::door_return`]; for that to cover the handler's locals, the
+/// handler must have run in a frame of its own, below the caller's.
+///
+/// # Safety
+///
+/// `argp` must be null or point to `arg_size` writable bytes, as it does for
+/// the arguments the kernel hands a server procedure.
+#[inline(never)]
+pub unsafe fn zeroize<const DEPTH: usize, C: AsRef<[u8]>>(
+    argp: *mut u8,
+    arg_size: usize,
+    response: &Response<C>,
+) {
+    let mut stack = std::mem::MaybeUninit::<[u8; DEPTH]>::uninit();
+    ptr::write_bytes(stack.as_mut_ptr(), 0, 1);
+    std::hint::black_box(&mut stack);
+
+    if argp.is_null() || arg_size == 0 {
+        return;
+    }
+    let request = argp as usize..argp as usize + arg_size;
+    if let Some(data) = &response.data {
+        let data = data.as_ref();
+        let start = data.as_ptr() as usize;
+        if start < request.end && request.start < start + data.len() {
+            return;
+        }
+    }
+    ptr::write_bytes(argp, 0, arg_size);
+    std::hint::black_box(argp);
+}
+
+/// Shared state which knows how to answer door calls
+///
+/// Implement this for the state that a [`DoorServer`] owns. Every invocation of
+/// the server's door is dispatched to [`Serve::serve`], with the state at hand
+/// in [`Request::state`].
+pub trait Serve: Sync + Sized + 'static {
+    /// The kind of data this server sends back.
+    ///
+    /// This may borrow from the request, as a `Cow<'a, [u8]>` might.
+    type Data<'a>: AsRef<[u8]>;
+
+    /// Answer a single door call.
+    fn serve<'a>(
+        &'a self,
+        request: Request<'a, Self>,
+    ) -> Response<Self::Data<'a>>;
+
+    /// The door has no clients left.
+    ///
+    /// This is only called for doors created with [`DoorAttributes::unref`],
+    /// which happens at most once, or [`DoorAttributes::unref_multi`], which
+    /// happens each time the last client goes away. Unreferenced notifications
+    /// are never passed to [`Serve::serve`]. Does nothing by default.
+    fn unreferenced(&self) {}
+}
+
+/// Instrumentation for one invocation of a server procedure
+///
+/// With the `tracing` feature, this enters a `tracing` span named
+/// `door_invocation`, recording the handler's name, the size of the request's
+/// data, and how many descriptors came with it. Since `door_return` never
+/// returns, nothing left on a door thread's stack is ever dropped, so the span
+/// is kept by the thread rather than on its stack, and must be closed before
+/// the response goes back: with [`Invocation::exit`], or by answering with
+/// one of the [`Request::reply`] family, which close it themselves. The
+/// [`server_procedure`] macro and [`Serve`] do this for you. Without the
+/// feature, this is an empty type, and costs nothing.
+///
+/// With the `deadlock-guard` feature, it also notes which door this thread is
+/// serving, so that [`Client::call`] can refuse to call that same door from
+/// inside its own handler.
+///
+/// Exactly one return must occur per invocation: either [`Invocation::exit`]
+/// followed by [`Response::door_return`], or one of the [`Request::reply`]
+/// family, which close the invocation themselves. A `door_return` which
+/// succeeds never comes back, so there is no second return to catch. What can
+/// go wrong is answering around the invocation, say by calling
+/// [`Response::door_return`] from inside a [`server_procedure`] handler, which
+/// leaves the invocation open on a thread that later calls will reuse. In
+/// debug builds, [`door_return`] aborts the process with a message saying so
+/// if it is reached with an invocation still open. Procedures which never
+/// enter an `Invocation` are not checked.
+///
+/// [`server_procedure`]: crate::server_procedure
+pub struct Invocation(());
+
+impl Invocation {
+    /// Open a span for `handler`'s answer to `request`.
+    #[inline]
+    pub fn enter<S>(handler: &'static str, request: &Request<'_, S>) -> Self {
+        #[cfg(feature = "tracing")]
+        SPAN.with(|slot| {
+            // Whatever a procedure failed to close is closed here.
+            drop(slot.take());
+            let span = tracing::info_span!(
+                "door_invocation",
+                handler,
+                data_size = request.data.len(),
+                descriptors = request.descriptors.len(),
+            )
+            .entered();
+            slot.replace(Some(span));
+        });
+        #[cfg(not(feature = "tracing"))]
+        let _ = (handler, request);
+        #[cfg(debug_assertions)]
+        OPEN.with(|open| open.set(true));
+        #[cfg(feature = "deadlock-guard")]
+        SERVING.with(|serving| {
+            let query = illumos::door_h::DOOR_QUERY;
+            serving.set(illumos::door_info(query).ok().map(|info| info.id()))
+        });
+        Self(())
+    }
+
+    /// Close the span. Call this right before `door_return`.
+    #[inline]
+    pub fn exit(self) {
+        close_invocation();
+    }
+}
+
+#[cfg(feature = "tracing")]
+thread_local! {
+    /// The span of the invocation this thread is serving, if any.
+    static SPAN: RefCell<Option<tracing::span::EnteredSpan>> = const {
+        RefCell::new(None)
+    };
+}
+
+/// Close the invocation this thread is serving, if any, before it returns.
+fn close_invocation() {
+    #[cfg(feature = "tracing")]
+    if let Some(span) = SPAN.with(|slot| slot.take()) {
+        span.exit();
+    }
+    #[cfg(feature = "deadlock-guard")]
+    SERVING.with(|serving| serving.set(None));
+    #[cfg(debug_assertions)]
+    OPEN.with(|open| open.set(false));
+}
+
+#[cfg(debug_assertions)]
+thread_local! {
+    /// Whether this thread has entered an [`Invocation`] it has yet to close.
+    static OPEN: std::cell::Cell<bool> = const { std::cell::Cell::new(false) };
+}
+
+/// In debug builds, abort if this thread is about to answer an invocation
+/// without having closed it.
+#[inline]
+fn guard_return() {
+    #[cfg(debug_assertions)]
+    if OPEN.with(|open| open.get()) {
+        eprintln!(
+            "doors: door_return was reached with an invocation still open; a \
+             handler must answer exactly once, by returning its Response or \
+             with Request::reply"
+        );
+        std::process::abort();
+    }
+}
+
+#[cfg(feature = "deadlock-guard")]
+thread_local! {
+    /// The id of the door whose invocation this thread is serving, if any.
+    static SERVING: std::cell::Cell<Option<u64>> = const {
+        std::cell::Cell::new(None)
+    };
+}
+
+/// The id of the door whose invocation this thread is serving, if any.
+#[cfg(feature = "deadlock-guard")]
+pub(crate) fn serving() -> Option<u64> {
+    SERVING.with(|serving| serving.get())
+}
+
+/// Raw server procedure which hands each invocation to a [`Serve`]r.
+extern "C" fn dispatch<S: Serve>(
+    cookie: *const libc::c_void,
+    argp: *const libc::c_char,
+    arg_size: libc::size_t,
+    dp: *const door_desc_t,
+    n_desc: libc::c_uint,
+) {
+    let state = unsafe { &*(cookie as *const S) };
+    if argp == illumos::door_h::DOOR_UNREF_DATA {
+        // Nobody is waiting to hear about a panic here.
+        panic::catch_unwind(AssertUnwindSafe(|| state.unreferenced())).ok();
+        Response::<[u8; 0]>::empty().door_return()
+    }
+    let data = match arg_size {
+        0 => &[],
+        _ => unsafe { std::slice::from_raw_parts(argp as *const u8, arg_size) },
+    };
+    let descriptors = match n_desc {
+        0 => &[],
+        _ => unsafe { std::slice::from_raw_parts(dp, n_desc as usize) },
+    };
+    let request = Request {
+        cookie: cookie as u64,
+        data,
+        descriptors,
+        state,
+    };
+    let invocation = Invocation::enter(std::any::type_name::<S>(), &request);
+    // A panic must not unwind out of an extern "C" function, so report it to
+    // the client instead.
+    match panic::catch_unwind(AssertUnwindSafe(|| state.serve(request))) {
+        Ok(response) => {
+            invocation.exit();
+            response.door_return()
+        }
+        Err(payload) => {
+            invocation.exit();
+            Response::from_panic(payload).door_return()
+        }
+    }
+}
+
+/// A door server, batteries included
+///
+/// This ties together the state which answers door calls, the door itself, and
+/// its place on the filesystem. Create one with [`DoorServer::new`], then call
+/// [`DoorServer::run`] to serve until a [`ShutdownHandle`] says to stop. When a
+/// `DoorServer` is dropped, it detaches the door from the filesystem, removes
+/// the jamb, and revokes the door.
+///
+/// The state itself is never freed. Revoking a door does not wait for calls
+/// already in progress, so there is no moment at which it would be safe to do
+/// so; a server typically lives as long as its process anyway.
+pub struct DoorServer<S: Serve> {
+    state: &'static S,
+    path: PathBuf,
+    shutdown: ShutdownHandle,
+    door: Door,
+}
+
+impl<S: Serve> DoorServer<S> {
+    /// Create a door which dispatches to `state`, and install it at `path`.
+    ///
+    /// Like [`Door::force_install`], this will replace anything already at
+    /// `path`.
+    ///
+    /// If either step fails -- say, with [`Error::CreateDoor`] because the
+    /// process is out of descriptors -- nothing is left behind: no door, no
+    /// jamb, and `state` is dropped.
+    pub fn new<P: AsRef<Path>>(state: S, path: P) -> Result<Self, Error> {
+        Self::with_attributes(state, path, DoorAttributes::none())
+    }
+
+    /// Like [`DoorServer::new`], but create the door with `attrs`.
+    ///
+    /// Create the door with [`DoorAttributes::unref`] or
+    /// [`DoorAttributes::unref_multi`] to have [`Serve::unreferenced`] called.
+    /// Bear in mind that the door's place on the filesystem counts as a
+    /// reference of its own, so the server will not be notified until it is
+    /// dropped or shut down.
+    pub fn with_attributes<P: AsRef<Path>>(
+        state: S,
+        path: P,
+        attrs: DoorAttributes,
+    ) -> Result<Self, Error> {
+        let state = Box::into_raw(Box::new(state));
+        let cookie = state as u64;
+        let door = Door::create_with_cookie_and_attributes(
+            dispatch::<S>,
+            cookie,
+            attrs,
+        )
+        .and_then(|door| door.force_install(&path).map(|()| door));
+        let door = match door {
+            Ok(door) => door,
+            Err(e) => {
+                // The door never made it onto the filesystem, so nobody could
+                // have called it, and it has been revoked by now. Nothing else
+                // refers to the state.
+                drop(unsafe { Box::from_raw(state) });
+                return Err(e);
+            }
+        };
+        Ok(Self {
+            state: unsafe { &*state },
+            path: path.as_ref().to_path_buf(),
+            shutdown: ShutdownHandle::default(),
+            door,
+        })
+    }
+
+    /// The state which answers this server's door calls.
+    pub fn state(&self) -> &S {
+        self.state
+    }
+
+    /// The door that this server has installed.
+    pub fn door(&self) -> &Door {
+        &self.door
+    }
+
+    /// Where on the filesystem this server's door can be found.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Something which can tell this server to stop running, from any thread.
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        self.shutdown.clone()
+    }
+
+    /// Serve door calls until told to shut down, then clean up.
+    ///
+    /// Door calls are answered on door threads, not on the thread which calls
+    /// `run`, so this does nothing but wait.
+    pub fn run(self) {
+        self.shutdown.wait();
+    }
+
+    /// Stop serving right away, and clean up.
+    pub fn shutdown(self) {
+        drop(self)
+    }
+}
+
+impl<S: Serve> Drop for DoorServer<S> {
+    fn drop(&mut self) {
+        // The door itself is revoked when the `door` field is dropped.
+        uninstall(&self.path).ok();
+    }
+}
+
+/// Tells a [`DoorServer`] to stop running
+#[derive(Clone, Default)]
+pub struct ShutdownHandle(Arc<(Mutex<bool>, Condvar)>);
+
+impl ShutdownHandle {
+    /// Ask the server to stop. Its [`DoorServer::run`] will return.
+    pub fn shutdown(&self) {
+        let (lock, cvar) = &*self.0;
+        *lock.lock().unwrap() = true;
+        cvar.notify_all();
+    }
+
+    fn wait(&self) {
+        let (lock, cvar) = &*self.0;
+        let mut stop = lock.lock().unwrap();
+        while !*stop {
+            stop = cvar.wait(stop).unwrap();
+        }
+    }
+}
+
+fn create_new_file<P: AsRef<Path>>(path: P) -> io::Result<File> {
+    File::options()
+        .read(true)
+        .write(true)
+        .create_new(true)
+        .open(path)
+}
+
+/// Counts the jambs made by [`Door::force_install`], to keep their names apart.
+static JAMBS: AtomicU64 = AtomicU64::new(0);
+
+/// How the names of [`Door::force_install`]'s jambs for `path` begin.
+fn jamb_prefix(path: &Path) -> Option<Vec<u8>> {
+    let mut prefix = b".".to_vec();
+    prefix.extend_from_slice(path.file_name()?.as_bytes());
+    prefix.push(b'.');
+    Some(prefix)
+}
+
+/// The jamb that `path` links to, if [`Door::force_install`] made the link.
+fn linked_jamb(path: &Path) -> Option<PathBuf> {
+    let target = std::fs::read_link(path).ok()?;
+    let beside = target.parent() == Some(Path::new(""));
+    let ours = target
+        .as_os_str()
+        .as_bytes()
+        .starts_with(&jamb_prefix(path)?);
+    (beside && ours).then(|| path.with_file_name(target))
+}
+
+/// Point `path` at the jamb `name` beside it, replacing whatever is there.
+fn swap_link(name: &ffi::OsStr, path: &Path) -> io::Result<()> {
+    let mut link = name.to_owned();
+    link.push(".link");
+    let link = path.with_file_name(link);
+    std::os::unix::fs::symlink(name, &link)?;
+
+    // A rename cannot replace a mount point, so a door attached to `path`
+    // itself has to go first. Removing it detaches the door.
+    let removed = match std::fs::symlink_metadata(path) {
+        Ok(meta) if !meta.file_type().is_symlink() => {
+            std::fs::remove_file(path)
+        }
+        _ => Ok(()),
+    };
+    let renamed = removed.and_then(|()| std::fs::rename(&link, path));
+    if renamed.is_err() {
+        std::fs::remove_file(&link).ok();
+    }
+    renamed
+}
+
+/// Detach whatever door is installed at `path`, and remove it from the
+/// filesystem, along with its hidden jamb if [`Door::force_install`] put it
+/// there.
+///
+/// Owners of an installed door call this when dropped, to make the door
+/// unreachable by name before it is revoked.
+pub(crate) fn uninstall(path: &Path) -> Result<(), illumos::Error> {
+    let jamb = linked_jamb(path);
+    illumos::fdetach(jamb.as_deref().unwrap_or(path))?;
+    if let Some(jamb) = jamb {
+        std::fs::remove_file(jamb).ok();
+    }
+    std::fs::remove_file(path).ok();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use std::time::Instant;
+
+    #[test]
+    #[should_panic]
+    fn create_new_fails_if_file_exists() {
+        match File::create("/tmp/create_new_fail.txt") {
+            // If we can't create the "original" file, we want the test to fail,
+            // which means that we *don't* want to panic.
+            Err(e) => {
+                eprintln!("{:?}", e);
+                assert!(true)
+            }
             Ok(_file) => {
                 create_new_file("/tmp/create_new_fail.txt").unwrap();
             }
         }
     }
+
+    extern "C" fn hello(
+        _cookie: *const libc::c_void,
+        _argp: *const libc::c_char,
+        _arg_size: libc::size_t,
+        _dp: *const door_desc_t,
+        _n_desc: libc::c_uint,
+    ) {
+    }
+
+    extern "C" fn answer(
+        _cookie: *const libc::c_void,
+        _argp: *const libc::c_char,
+        _arg_size: libc::size_t,
+        _dp: *const door_desc_t,
+        _n_desc: libc::c_uint,
+    ) {
+        Response::new([42]).door_return()
+    }
+
+    extern "C" fn hand_written_answer(
+        _cookie: *const libc::c_void,
+        _argp: *const libc::c_char,
+        _arg_size: libc::size_t,
+        _dp: *const door_desc_t,
+        _n_desc: libc::c_uint,
+    ) {
+        door_return(&[42], &[])
+    }
+
+    extern "C" fn three_nulls(
+        _cookie: *const libc::c_void,
+        _argp: *const libc::c_char,
+        _arg_size: libc::size_t,
+        _dp: *const door_desc_t,
+        _n_desc: libc::c_uint,
+    ) {
+        let nulls = (0..3).map(|_| {
+            let null = File::open("/dev/null").unwrap();
+            DoorFd::new(null.into_raw_fd(), true)
+        });
+        Response::from_descriptors([3], nulls).door_return()
+    }
+
+    fn is_cloexec(fd: RawFd) -> bool {
+        let flags = unsafe { libc::fcntl(fd, libc::F_GETFD) };
+        assert_ne!(flags, -1);
+        flags & libc::FD_CLOEXEC != 0
+    }
+
+    #[test]
+    fn doors_are_created_cloexec() {
+        let door = Door::create(hello).unwrap();
+        assert!(is_cloexec(door.as_raw_fd()));
+    }
+
+    #[test]
+    fn private_doors_are_private() {
+        let door = Door::create_private(hello, 2).unwrap();
+        let info = illumos::door_info(door.as_raw_fd()).unwrap();
+        let attrs = info.attributes().get();
+        assert_ne!(attrs & illumos::door_h::DOOR_PRIVATE, 0);
+    }
+
+    #[test]
+    fn private_doors_answer_calls() {
+        let door = Door::create_private(answer, 1).unwrap();
+        let mut rbuf = [0];
+        let arg = crate::DoorArgument::new(&[], &[], &mut rbuf);
+        let rc = unsafe {
+            illumos::door_h::door_call(door.as_raw_fd(), arg.as_door_arg_t())
+        };
+        assert_eq!(rc, 0);
+        assert_eq!(arg.data(), &[42]);
+    }
+
+    #[test]
+    fn bound_threads_learn_when_their_door_is_gone() {
+        let door =
+            Door::create_with_attributes(hello, DoorAttributes::private())
+                .unwrap();
+        let fd = door.as_raw_fd();
+        let (tx, rx) = mpsc::channel();
+        let waiter = thread::spawn(move || {
+            illumos::door_bind(fd).unwrap();
+            tx.send(()).unwrap();
+            illumos::door_wait()
+        });
+        rx.recv().unwrap();
+        drop(door);
+        assert_eq!(waiter.join().unwrap(), illumos::Error::DoorGone);
+    }
+
+    #[test]
+    fn doors_know_their_procedure() {
+        let door = Door::create_with_cookie(hand_written_answer, 9).unwrap();
+        let info = door.info().unwrap();
+        let expected = hand_written_answer as illumos::ServerProcedure;
+        assert_eq!(info.proc(), expected as *const illumos::ServerProcedure);
+        assert_eq!(info.cookie(), 9);
+        assert!(info.belongs_to_current_process());
+    }
+
+    #[test]
+    fn builders_combine_options() {
+        let door = Door::builder()
+            .cookie(7)
+            .attributes(DoorAttributes::refuse_desc())
+            .private_pool(1)
+            .build(hand_written_answer)
+            .unwrap();
+        let info = door.info().unwrap();
+        assert_eq!(info.cookie(), 7);
+        assert!(info.is_private());
+        assert!(info.attributes().contains(DoorAttributes::refuse_desc()));
+    }
+
+    static SHUTDOWN: Shutdown = Shutdown::new();
+
+    extern "C" fn shut_down(
+        _cookie: *const libc::c_void,
+        _argp: *const libc::c_char,
+        _arg_size: libc::size_t,
+        _dp: *const door_desc_t,
+        _n_desc: libc::c_uint,
+    ) {
+        SHUTDOWN.request();
+        door_return(&[], &[])
+    }
+
+    #[test]
+    fn doors_wait_for_shutdown() {
+        let path = std::env::temp_dir().join("doors_wait_for_shutdown.door");
+        let door = Door::create(shut_down).unwrap();
+        door.force_install(&path).unwrap();
+        let client = Client::open(&path).unwrap();
+
+        let waiting = {
+            let path = path.clone();
+            thread::spawn(move || door.wait_for_shutdown(path, &SHUTDOWN))
+        };
+        assert!(!SHUTDOWN.is_requested());
+
+        client.call_with_data(b"shutdown").unwrap();
+        waiting.join().unwrap().unwrap();
+        assert!(SHUTDOWN.is_requested());
+        assert!(!path.exists());
+        let arg = crate::DoorArgument::new(&[], &[], &mut []);
+        assert_eq!(
+            client.call(arg).unwrap_err(),
+            crate::DoorCallError::Revoked
+        );
+    }
+
+    #[test]
+    fn doors_become_owned_descriptors_unrevoked() {
+        let door = Door::create(hand_written_answer).unwrap();
+        let fd = unsafe { libc::dup(door.as_raw_fd()) };
+        let client = unsafe { Client::from_raw_fd(fd) };
+
+        let owned = OwnedFd::from(door);
+        assert!(illumos::door_info(owned.as_raw_fd()).is_ok());
+        drop(owned);
+
+        // Closing the descriptor did not revoke the door.
+        let response = client.call_with_data(&[]).unwrap();
+        assert_eq!(response.data(), &[42]);
+    }
+
+    #[test]
+    fn mapped_responses_keep_their_descriptors() {
+        let response = Response::new([1u8, 2, 3])
+            .add_descriptor(0, false)
+            .add_descriptor(1, false);
+        let mapped = response.map(|data| data.iter().rev().copied().collect());
+        assert_eq!(mapped.data, Some(vec![3, 2, 1]));
+        assert_eq!(mapped.descriptors.len(), 2);
+        assert_eq!(mapped.descriptors[1].as_raw_fd(), 1);
+
+        let empty = Response::<[u8; 0]>::empty()
+            .map(|_| -> Vec<u8> { panic!("there is no data to map") });
+        assert!(empty.data.is_none());
+    }
+
+    #[test]
+    fn shared_state_is_seen_across_fork() {
+        // Safety: an atomic holds no pointers.
+        let counter = unsafe { Shared::new(AtomicU64::new(1)) }.unwrap();
+        match unsafe { libc::fork() } {
+            -1 => panic!("fork failed"),
+            0 => {
+                counter.fetch_add(41, Ordering::SeqCst);
+                unsafe { libc::_exit(0) }
+            }
+            child => {
+                let mut status = 0;
+                assert_eq!(
+                    unsafe { libc::waitpid(child, &mut status, 0) },
+                    child
+                );
+                assert_eq!(counter.load(Ordering::SeqCst), 42);
+            }
+        }
+    }
+
+    #[test]
+    fn both_paths_answer_during_reinstall() {
+        let old = Path::new("/tmp/reinstall_window_old.door");
+        let new = Path::new("/tmp/reinstall_window_new.door");
+        std::fs::remove_file(new).ok();
+        let door = Door::create(answer).unwrap();
+        door.force_install(old).unwrap();
+
+        let call = |path: &Path| {
+            let client = Client::open(path).unwrap();
+            let arg = crate::DoorArgument::new(&[], &[], &mut []);
+            client.call(arg).unwrap().data().to_vec()
+        };
+        door.reinstall_with(old, new, || {
+            assert_eq!(call(old), [42]);
+            assert_eq!(call(new), [42]);
+        })
+        .unwrap();
+        assert!(!old.exists());
+        assert_eq!(call(new), [42]);
+
+        uninstall(new).unwrap();
+    }
+
+    #[test]
+    fn shared_state_must_fit_a_page_alignment() {
+        #[repr(align(65536))]
+        struct Aligned;
+
+        let result = unsafe { Shared::new(Aligned) };
+        let e = result.err().unwrap();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn doors_know_whether_they_are_referenced() {
+        let door = Door::create(hand_written_answer).unwrap();
+        assert!(!door.is_referenced().unwrap());
+
+        let client = door.loopback_client().unwrap();
+        assert!(door.is_referenced().unwrap());
+
+        drop(client);
+        assert!(!door.is_referenced().unwrap());
+    }
+
+    #[test]
+    fn loopback_clients_call_the_door_in_process() {
+        let door = Door::create(hand_written_answer).unwrap();
+        let client = door.loopback_client().unwrap();
+        assert_eq!(client.call_with_data(&[]).unwrap(), [42]);
+
+        // Dropping the client leaves the door open for others.
+        drop(client);
+        let client = door.loopback_client().unwrap();
+        assert_eq!(client.call_with_data(&[]).unwrap(), [42]);
+
+        drop(door);
+        let result = client.call_with_data(&[]);
+        assert!(matches!(result, Err(crate::DoorCallError::Revoked)));
+    }
+
+    #[test]
+    fn hand_written_procedures_can_door_return() {
+        let door = Door::create(hand_written_answer).unwrap();
+        let arg = crate::DoorArgument::new(&[], &[], &mut []);
+        let response = door.call_self(arg).unwrap();
+        assert_eq!(response.data(), &[42]);
+    }
+
+    #[test]
+    fn responses_collect_descriptors() {
+        let door = Door::create(three_nulls).unwrap();
+        let arg = crate::DoorArgument::new(&[], &[], &mut []);
+        let response = door.call_self(arg).unwrap();
+        assert_eq!(response.data(), &[3]);
+        assert_eq!(response.into_descriptors().count(), 3);
+
+        let fds = [DoorFd::new(0, false), DoorFd::new(1, false)];
+        let mut response: Response<[u8; 0]> = fds.into_iter().collect();
+        assert!(response.data.is_none());
+        response.extend([DoorFd::new(2, false)]);
+        assert_eq!(response.descriptors.len(), 3);
+    }
+
+    struct ThreeNulls;
+
+    impl Serve for ThreeNulls {
+        type Data<'a> = [u8; 1];
+
+        fn serve(&self, _request: Request<'_, Self>) -> Response<[u8; 1]> {
+            let nulls = (0..3).map(|_| {
+                let null = File::open("/dev/null").unwrap();
+                DoorFd::from_owned(null.into())
+            });
+            Response::from_descriptors([3], nulls)
+        }
+    }
+
+    #[test]
+    fn served_responses_carry_every_descriptor() {
+        static STATE: ThreeNulls = ThreeNulls;
+        let door = Door::create_with_cookie(
+            dispatch::<ThreeNulls>,
+            &STATE as *const ThreeNulls as u64,
+        )
+        .unwrap();
+
+        let arg = crate::DoorArgument::new(&[], &[], &mut []);
+        let response = door.call_self(arg).unwrap();
+        assert_eq!(response.data(), &[3]);
+        let nulls: Vec<OwnedFd> = response.into_descriptors().collect();
+        assert_eq!(nulls.len(), 3);
+        for null in nulls {
+            let mut file = File::from(null);
+            let mut buf = [0; 1];
+            assert_eq!(io::Read::read(&mut file, &mut buf).unwrap(), 0);
+        }
+    }
+
+    #[test]
+    fn doors_can_call_themselves() {
+        let door = Door::create(answer).unwrap();
+        let mut rbuf = [0];
+        let arg = crate::DoorArgument::new(&[], &[], &mut rbuf);
+        let response = door.call_self(arg).unwrap();
+        assert_eq!(response.data(), &[42]);
+    }
+
+    #[test]
+    fn cloned_doors_answer_at_both_paths() {
+        let door = Door::create(answer).unwrap();
+        let clone = door.try_clone().unwrap();
+        door.force_install("/tmp/try_clone_original.door").unwrap();
+        clone.force_install("/tmp/try_clone_clone.door").unwrap();
+
+        for path in
+            ["/tmp/try_clone_original.door", "/tmp/try_clone_clone.door"]
+        {
+            let client = Client::open(path).unwrap();
+            let response = client.call_with_data(&[]).unwrap();
+            assert_eq!(response.data(), &[42]);
+        }
+    }
+
+    #[test]
+    fn doors_can_be_installed_at_many_paths() {
+        let paths =
+            ["/tmp/install_at_many_a.door", "/tmp/install_at_many_b.door"];
+        for path in paths {
+            std::fs::remove_file(path).ok();
+        }
+        let door = Door::create(answer).unwrap();
+        let clones = door.install_at_many(&paths).unwrap();
+        assert_eq!(clones.len(), 2);
+
+        for path in paths {
+            let client = Client::open(path).unwrap();
+            let response = client.call_with_data(&[]).unwrap();
+            assert_eq!(response.data(), &[42]);
+        }
+    }
+
+    #[test]
+    fn install_at_many_rolls_back_on_failure() {
+        let paths = [
+            "/tmp/install_at_many_rollback.door",
+            "/tmp/install_at_many_taken.door",
+        ];
+        std::fs::remove_file(paths[0]).ok();
+        File::create(paths[1]).unwrap();
+
+        let door = Door::create(answer).unwrap();
+        match door.install_at_many(&paths) {
+            Err(Error::InstallJamb(_)) => (),
+            _ => panic!("second install should have failed"),
+        }
+        assert!(!Path::new(paths[0]).exists());
+
+        // The rollback must not have revoked the door itself.
+        let arg = crate::DoorArgument::new(&[], &[], &mut []);
+        assert!(door.call_self(arg).is_ok());
+    }
+
+    #[test]
+    fn dropping_a_clone_revokes_the_door() {
+        let door = Door::create(answer).unwrap();
+        let clone = door.try_clone().unwrap();
+        drop(clone);
+
+        let arg = crate::DoorArgument::new(&[], &[], &mut []);
+        match door.call_self(arg) {
+            Err(e) => assert_eq!(e, crate::DoorCallError::Revoked),
+            Ok(_) => panic!("door should have been revoked"),
+        }
+    }
+
+    struct Echo;
+
+    impl Serve for Echo {
+        type Data<'a> = [u8; 1];
+
+        fn serve(&self, request: Request<'_, Self>) -> Response<[u8; 1]> {
+            Response::new([request.data[0]])
+        }
+    }
+
+    static UNREFERENCED: std::sync::atomic::AtomicUsize =
+        std::sync::atomic::AtomicUsize::new(0);
+
+    struct Unreferenced;
+
+    impl Serve for Unreferenced {
+        type Data<'a> = [u8; 0];
+
+        fn serve(&self, _request: Request<'_, Self>) -> Response<[u8; 0]> {
+            panic!("notifications should not be served as calls");
+        }
+
+        fn unreferenced(&self) {
+            UNREFERENCED.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        }
+    }
+
+    struct Fragile;
+
+    impl Serve for Fragile {
+        type Data<'a> = [u8; 0];
+
+        fn serve(&self, _request: Request<'_, Self>) -> Response<[u8; 0]> {
+            panic!("fragile servers break");
+        }
+    }
+
+    #[test]
+    fn serve_panics_are_reported_to_the_client() {
+        static STATE: Fragile = Fragile;
+        let door = Door::create_with_cookie_and_attributes(
+            dispatch::<Fragile>,
+            &STATE as *const Fragile as u64,
+            DoorAttributes::none(),
+        )
+        .unwrap();
+
+        let response =
+            door.call_self(crate::DoorArgument::new(&[], &[], &mut []));
+        let error = response.unwrap().as_result().unwrap_err();
+        assert!(error.is_server_panic());
+        assert_eq!(error.detail, b"fragile servers break");
+    }
+
+    #[test]
+    fn unreferenced_notifications_reach_serve() {
+        static STATE: Unreferenced = Unreferenced;
+        let door = Door::create_with_cookie_and_attributes(
+            dispatch::<Unreferenced>,
+            &STATE as *const Unreferenced as u64,
+            DoorAttributes::unref_multi(),
+        )
+        .unwrap();
+
+        for expected in 1..=3 {
+            let fd = unsafe { libc::dup(door.as_raw_fd()) };
+            assert_ne!(fd, -1);
+            unsafe { libc::close(fd) };
+            let deadline = Instant::now() + Duration::from_secs(5);
+            while UNREFERENCED.load(std::sync::atomic::Ordering::SeqCst)
+                < expected
+            {
+                assert!(Instant::now() < deadline);
+                thread::sleep(Duration::from_millis(10));
+            }
+        }
+    }
+
+    #[test]
+    fn door_servers_serve_and_clean_up() {
+        let path = Path::new("/tmp/door_server_unit.door");
+        let server = DoorServer::new(Echo, path).unwrap();
+
+        let client = Client::open(path).unwrap();
+        let response = client.call_with_data(&[7]).unwrap();
+        assert_eq!(response.data(), &[7]);
+
+        server.shutdown();
+        assert!(!path.exists());
+        let result = client.call_with_data(&[7]);
+        assert_eq!(result.unwrap_err(), crate::DoorCallError::Revoked);
+    }
+
+    struct Flagged(Arc<std::sync::atomic::AtomicBool>);
+
+    impl Serve for Flagged {
+        type Data<'a> = [u8; 0];
+
+        fn serve(&self, _request: Request<'_, Self>) -> Response<[u8; 0]> {
+            Response::empty()
+        }
+    }
+
+    impl Drop for Flagged {
+        fn drop(&mut self) {
+            self.0.store(true, std::sync::atomic::Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn failed_door_servers_drop_their_state() {
+        let dropped = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let state = Flagged(dropped.clone());
+        let path = "/tmp/no/such/directory/door_server.door";
+        assert!(DoorServer::new(state, path).is_err());
+        assert!(dropped.load(std::sync::atomic::Ordering::SeqCst));
+    }
+
+    #[test]
+    fn install_creates_missing_parents() {
+        let dir = Path::new("/tmp/install_with_parents");
+        std::fs::remove_dir_all(dir).ok();
+        let path = dir.join("nested").join("hello.door");
+
+        let door = Door::create(hello).unwrap();
+        door.install_with_parents(&path).unwrap();
+        assert!(path.exists());
+
+        unsafe {
+            let c_path =
+                ffi::CString::new(path.as_os_str().as_bytes()).unwrap();
+            illumos::stropts_h::fdetach(c_path.as_ptr());
+        }
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn install_accepts_non_utf8_paths() {
+        let path =
+            Path::new(ffi::OsStr::from_bytes(b"/tmp/non_utf8_\xff.door"));
+        std::fs::remove_file(path).ok();
+
+        let door = Door::create(hello).unwrap();
+        door.install(path).unwrap();
+        let client = Client::open(path).unwrap();
+        assert!(client.call_with_data(&[]).is_ok());
+
+        illumos::fdetach(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn install_refuses_nul_in_paths() {
+        let door = Door::create(hello).unwrap();
+        let result = door.install("/tmp/nul\0in_path.door");
+        assert!(matches!(result, Err(Error::InstallJamb(_))));
+        assert!(Client::open("/tmp/nul\0in_path.door").is_err());
+    }
+
+    #[test]
+    fn install_refuses_a_file_as_parent() {
+        let file = Path::new("/tmp/install_with_parents.txt");
+        File::create(file).unwrap();
+        let path = file.join("hello.door");
+
+        let door = Door::create(hello).unwrap();
+        let result = door.install_with_parents(path);
+        assert!(matches!(result, Err(Error::CreateParent(_))));
+    }
+
+    #[test]
+    fn doors_can_opt_out_of_cloexec() {
+        let door = Door::create(hello).unwrap();
+        door.set_cloexec(false).unwrap();
+        assert!(!is_cloexec(door.as_raw_fd()));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn invocations_stay_open_until_closed() {
+        let request = Request {
+            cookie: 0,
+            data: &[],
+            descriptors: &[],
+            state: &(),
+        };
+        let open = || OPEN.with(|open| open.get());
+        assert!(!open());
+
+        let invocation = Invocation::enter("once", &request);
+        assert!(open());
+        invocation.exit();
+        assert!(!open());
+
+        let _invocation = Invocation::enter("again", &request);
+        assert!(open());
+        close_invocation();
+        assert!(!open());
+    }
 }
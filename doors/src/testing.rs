@@ -0,0 +1,121 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ *
+ * Copyright 2023 Robert D. French
+ */
+//! Helpers for testing door servers and clients.
+//!
+//! This module is only available with the `testing` feature enabled. It is
+//! meant for test code, whether in this crate or downstream:
+//!
+//! ```toml
+//! [dev-dependencies]
+//! doors = { version = "0.8", features = ["testing"] }
+//! ```
+
+use crate::illumos::ServerProcedure;
+use crate::server;
+use crate::server::Door;
+use crate::server::Error;
+use crate::Client;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+/// Create a door, and a client connected straight to it
+///
+/// Door descriptors can be called directly, so a door does not need to be on
+/// the filesystem for this process to call it. The client here comes from
+/// [`Door::loopback_client`], and holds its own duplicate of the door's
+/// descriptor: nothing is installed, and there is nothing to clean up beyond
+/// dropping the pair. Dropping the [`Door`] revokes
+/// it, after which calls through the client fail with
+/// [`DoorCallError::Revoked`][crate::DoorCallError::Revoked].
+///
+/// ```
+/// use doors::server::{Request, Response};
+///
+/// #[doors::server_procedure]
+/// fn double(x: Request<'_>) -> Response<[u8; 1]> {
+///     Response::new([x.data[0] * 2])
+/// }
+///
+/// let (_door, client) = doors::testing::channel(double).unwrap();
+/// let response = client.call_with_data(&[111]).unwrap();
+/// assert_eq!(response.data(), &[222]);
+/// ```
+pub fn channel(sp: ServerProcedure) -> Result<(Door, Client), Error> {
+    let door = Door::create(sp)?;
+    let client = door.loopback_client().map_err(Error::CloneDoor)?;
+    Ok((door, client))
+}
+
+/// Number of `TempDoor`s this process has created so far.
+static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// A door installed at a unique temporary path, for as long as it lives
+///
+/// Tests which install their doors at fixed paths like `/tmp/double.door` trip
+/// over each other when run concurrently, and leave files behind when they
+/// fail. A `TempDoor` instead installs its door at a path no other `TempDoor`
+/// will use -- it includes the process id and a per-process counter -- and
+/// cleans up after itself: when dropped, it detaches the door from the
+/// filesystem, removes the jamb, and revokes the door.
+///
+/// ```
+/// use doors::server::{Door, Request, Response};
+/// use doors::testing::TempDoor;
+///
+/// #[doors::server_procedure]
+/// fn double(x: Request<'_>) -> Response<[u8; 1]> {
+///     Response::new([x.data[0] * 2])
+/// }
+///
+/// let door = TempDoor::new(Door::create(double).unwrap()).unwrap();
+/// let response = door.client().unwrap().call_with_data(&[111]).unwrap();
+/// assert_eq!(response.data(), &[222]);
+/// ```
+pub struct TempDoor {
+    path: PathBuf,
+    door: Door,
+}
+
+impl TempDoor {
+    /// Install `door` at a fresh path in the system's temporary directory.
+    ///
+    /// This takes an already-created [`Door`], so that doors with attributes,
+    /// cookies, or state can be tested just like plain ones.
+    pub fn new(door: Door) -> Result<Self, Error> {
+        let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let name = format!("doors-{}-{}.door", std::process::id(), n);
+        let path = std::env::temp_dir().join(name);
+        door.install(&path)?;
+        Ok(Self { path, door })
+    }
+
+    /// Where this door has been installed.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The door being tested.
+    pub fn door(&self) -> &Door {
+        &self.door
+    }
+
+    /// Open a new client for this door.
+    pub fn client(&self) -> io::Result<Client> {
+        Client::open(&self.path)
+    }
+}
+
+impl Drop for TempDoor {
+    fn drop(&mut self) {
+        // The door itself is revoked when the `door` field is dropped.
+        server::uninstall(&self.path).ok();
+    }
+}
@@ -0,0 +1,118 @@
+/*
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/.
+ *
+ * Copyright 2023 Robert D. French
+ */
+//! Plain old data, sent through doors as is.
+//!
+//! Doors which pass fixed-layout `#[repr(C)]` values back and forth can skip
+//! parsing altogether, and just copy the bytes. That is only sound for types
+//! which promise, by implementing the unsafe traits here, that their bytes can
+//! be read and written directly. See
+//! [`server_procedure_typed`][crate::server_procedure_typed] for the server
+//! side, and [`Client::call_typed`][crate::Client::call_typed] for the client.
+//!
+//! ```
+//! use doors::typed::{AsBytes, FromBytes};
+//!
+//! #[derive(Clone, Copy)]
+//! #[repr(C)]
+//! struct Transfer {
+//!     amount: u64,
+//!     from: u32,
+//!     to: u32,
+//! }
+//!
+//! // Safety: `Transfer` is repr(C), has no padding, and is made of integers.
+//! unsafe impl AsBytes for Transfer {}
+//! unsafe impl FromBytes for Transfer {}
+//! ```
+//!
+//! Rather than implement the traits by hand, derive them with
+//! [`DoorPayload`][crate::DoorPayload], which checks those promises at compile
+//! time.
+//!
+//! Values are copied out of the request with an explicit length check, and
+//! without assuming the bytes are aligned, so a client which sends the wrong
+//! thing gets an error rather than undefined behavior.
+
+use crate::DoorCallError;
+use crate::DoorError;
+use std::mem;
+use std::ptr;
+
+/// Types whose bytes can be sent as they are.
+///
+/// # Safety
+///
+/// Every byte of the type must be initialized, so it must not have any
+/// padding, and it must not contain pointers or references, which mean
+/// nothing in another process. A `#[repr(C)]` struct of integers laid out
+/// without gaps is the typical example.
+pub unsafe trait AsBytes: Copy + 'static {
+    /// The bytes of this value.
+    fn as_bytes(&self) -> &[u8] {
+        let len = mem::size_of::<Self>();
+        unsafe {
+            std::slice::from_raw_parts(self as *const Self as *const u8, len)
+        }
+    }
+}
+
+/// Types which can be made from any bytes of the right length.
+///
+/// # Safety
+///
+/// Every bit pattern must be a valid value of the type. Integers and arrays of
+/// them qualify; `bool`, `char`, enums and references do not.
+pub unsafe trait FromBytes: Copy + 'static {
+    /// Copy a value out of `bytes`, which must be exactly the right length.
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        match bytes.len() == mem::size_of::<Self>() {
+            true => Some(unsafe {
+                ptr::read_unaligned(bytes.as_ptr() as *const Self)
+            }),
+            false => None,
+        }
+    }
+}
+
+macro_rules! plain {
+    ($($t:ty),*) => {
+        $(
+            unsafe impl AsBytes for $t {}
+            unsafe impl FromBytes for $t {}
+        )*
+    };
+}
+
+plain!(
+    u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64
+);
+
+unsafe impl<T: AsBytes, const N: usize> AsBytes for [T; N] {}
+unsafe impl<T: FromBytes, const N: usize> FromBytes for [T; N] {}
+
+/// Ways in which [`Client::call_typed`][crate::Client::call_typed] can fail
+#[derive(Debug, PartialEq)]
+pub enum TypedCallError {
+    /// The door call itself failed.
+    Call(DoorCallError),
+
+    /// The server answered with an error frame. A typed procedure sends one
+    /// with code [`DoorError::WRONG_SIZE`] when the request is the wrong size,
+    /// and one with code [`DoorError::SERVER_PANICKED`] if its handler panics.
+    Server(DoorError),
+
+    /// The response was this many bytes long, which is the wrong size for the
+    /// type expected.
+    WrongSize(usize),
+}
+
+impl From<DoorCallError> for TypedCallError {
+    fn from(e: DoorCallError) -> Self {
+        Self::Call(e)
+    }
+}
@@ -0,0 +1,55 @@
+use doors::server::{Request, Response};
+use doors::testing::channel;
+use doors::DoorCallError;
+use std::time::{Duration, Instant};
+
+#[doors::server_procedure]
+fn echo(x: Request<'_>) -> Response<&[u8]> {
+    Response::new(x.data)
+}
+
+#[test]
+fn calls_past_the_deadline_are_not_made() {
+    let (_door, echo) = channel(echo).unwrap();
+
+    let past = Instant::now() - Duration::from_millis(1);
+    let result = doors::deadline::with(past, || echo.call_with_data(b"late"));
+    assert_eq!(result.unwrap_err(), DoorCallError::TimedOut);
+
+    // The deadline is gone again once `with` returns.
+    assert_eq!(doors::deadline::get(), None);
+    assert_eq!(echo.call_with_data(b"on time").unwrap(), b"on time");
+}
+
+#[test]
+fn timed_calls_past_the_deadline_are_not_made() {
+    let (_door, echo) = channel(echo).unwrap();
+
+    let past = Instant::now() - Duration::from_millis(1);
+    let result = doors::deadline::with(past, || {
+        let arg = doors::DoorArgument::new(b"late", &[], &mut []);
+        echo.call_timed(arg).map(|_| ())
+    });
+    assert_eq!(result.unwrap_err(), DoorCallError::TimedOut);
+}
+
+#[test]
+fn calls_before_the_deadline_are_made() {
+    let (_door, echo) = channel(echo).unwrap();
+
+    let future = Instant::now() + Duration::from_secs(60);
+    let result = doors::deadline::with(future, || echo.call_with_data(b"hi"));
+    assert_eq!(result.unwrap(), b"hi");
+}
+
+#[test]
+fn deadlines_belong_to_their_thread() {
+    let (_door, echo) = channel(echo).unwrap();
+    doors::deadline::set(Some(Instant::now()));
+
+    std::thread::scope(|s| {
+        s.spawn(|| assert!(echo.call_with_data(b"elsewhere").is_ok()));
+    });
+    assert!(echo.call_with_data(b"here").is_err());
+    doors::deadline::set(None);
+}
@@ -0,0 +1,49 @@
+use doors::server::{ArcState, Door, Request, Response};
+use doors::DoorArgument;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+#[derive(Default)]
+struct Counter(AtomicU32);
+
+#[doors::server_procedure(state = ArcState<Counter>)]
+fn count(x: Request<'_, ArcState<Counter>>) -> Response<[u8; 4]> {
+    match x.arc_state() {
+        Some(counter) => {
+            Response::scalar(counter.0.fetch_add(1, Ordering::SeqCst))
+        }
+        None => Response::scalar(u32::MAX),
+    }
+}
+
+#[test]
+fn arc_state_is_released_with_the_door() {
+    let counter = Arc::new(Counter::default());
+    let door = Door::create_with_arc(count, counter.clone()).unwrap();
+    assert_eq!(Arc::strong_count(&counter), 2);
+
+    for expected in 0..3 {
+        let response = door
+            .call_self(DoorArgument::new(&[], &[], &mut []))
+            .unwrap();
+        assert_eq!(response.as_u32(), Some(expected));
+    }
+    assert_eq!(counter.0.load(Ordering::SeqCst), 3);
+
+    // Handlers give their references back when they finish.
+    assert_eq!(Arc::strong_count(&counter), 2);
+    drop(door);
+    assert_eq!(Arc::strong_count(&counter), 1);
+}
+
+#[test]
+fn clones_release_the_state_once() {
+    let counter = Arc::new(Counter::default());
+    let door = Door::create_with_arc(count, counter.clone()).unwrap();
+    let clone = door.try_clone().unwrap();
+
+    drop(door);
+    assert_eq!(Arc::strong_count(&counter), 1);
+    drop(clone);
+    assert_eq!(Arc::strong_count(&counter), 1);
+}
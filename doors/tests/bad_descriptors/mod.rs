@@ -0,0 +1,33 @@
+use doors::server::{Door, Request, Response};
+use doors::Client;
+use doors::DoorCallError;
+use std::os::fd::AsRawFd;
+use std::os::fd::FromRawFd;
+
+#[doors::server_procedure]
+fn ignore(_x: Request<'_>) -> Response<[u8; 0]> {
+    Response::empty()
+}
+
+#[test]
+fn calling_a_regular_file_is_not_a_door() {
+    let client = Client::open("/dev/null").unwrap();
+    match client.call_with_data(&[]) {
+        Err(e) => assert_eq!(e, DoorCallError::NotADoor),
+        Ok(_) => panic!("/dev/null is not a door"),
+    }
+}
+
+#[test]
+fn calling_a_revoked_door_is_revoked() {
+    let door = Door::create(ignore).unwrap();
+    let fd = unsafe { libc::dup(door.as_raw_fd()) };
+    assert_ne!(fd, -1);
+    let client = unsafe { Client::from_raw_fd(fd) };
+    drop(door);
+
+    match client.call_with_data(&[]) {
+        Err(e) => assert_eq!(e, DoorCallError::Revoked),
+        Ok(_) => panic!("door should have been revoked"),
+    }
+}
@@ -87,8 +87,7 @@ fn new_door_arg() {
     }
     assert_eq!(rc, 0);
     assert_eq!(args.data().len(), 14);
-    let response = std::ffi::CStr::from_bytes_with_nul(args.data()).unwrap();
-    let response = response.to_str().unwrap();
+    let response = args.as_cstr().unwrap().to_str().unwrap();
     assert_eq!(response, "HELLO, WORLD!");
 }
 
@@ -104,8 +103,7 @@ fn dropped_doors_are_invalid() {
 
     let args = door.call(args).unwrap();
     assert_eq!(args.data().len(), 14);
-    let response = std::ffi::CStr::from_bytes_with_nul(args.data()).unwrap();
-    let response = response.to_str().unwrap();
+    let response = args.as_cstr().unwrap().to_str().unwrap();
     assert_eq!(response, "HELLO, WORLD!");
 
     drop(door);
@@ -129,10 +127,7 @@ fn open_door_from_path() {
     let door = Client::open("/tmp/barebones_capitalize.door").unwrap();
 
     let args = door.call(args).unwrap();
-    assert_eq!(args.data().len(), 14);
-    let response = std::ffi::CStr::from_bytes_with_nul(args.data()).unwrap();
-    let response = response.to_str().unwrap();
-    assert_eq!(response, "HELLO, WORLD!");
+    assert_eq!(args, b"HELLO, WORLD!\0");
 }
 
 #[test]
@@ -145,8 +140,37 @@ fn call_door() {
     let door = unsafe { Client::from_raw_fd(file.as_raw_fd()) };
 
     let args = door.call(args).unwrap();
+    assert_eq!(args, b"HELLO, WORLD!\0");
+}
+
+fn is_cloexec(fd: std::os::fd::RawFd) -> bool {
+    let flags = unsafe { libc::fcntl(fd, libc::F_GETFD) };
+    assert_ne!(flags, -1);
+    flags & libc::FD_CLOEXEC != 0
+}
+
+#[test]
+fn clients_are_opened_cloexec() {
+    let door = Client::open("/tmp/barebones_capitalize.door").unwrap();
+    assert!(is_cloexec(door.as_raw_fd()));
+}
+
+#[test]
+fn clients_can_opt_out_of_cloexec() {
+    let door = Client::open("/tmp/barebones_capitalize.door").unwrap();
+    door.set_cloexec(false).unwrap();
+    assert!(!is_cloexec(door.as_raw_fd()));
+}
+
+#[test]
+fn timed_call() {
+    let source = CString::new("Hello, World!").unwrap();
+    let text = source.to_bytes_with_nul();
+    let mut buffer = [0; 1024];
+    let args = DoorArgument::new(text, &[], &mut buffer);
+    let door = Client::open("/tmp/barebones_capitalize.door").unwrap();
+
+    let (args, elapsed) = door.call_timed(args).unwrap();
     assert_eq!(args.data().len(), 14);
-    let response = std::ffi::CStr::from_bytes_with_nul(args.data()).unwrap();
-    let response = response.to_str().unwrap();
-    assert_eq!(response, "HELLO, WORLD!");
+    assert!(elapsed > std::time::Duration::ZERO);
 }
@@ -0,0 +1,33 @@
+use doors::server::{Request, Response};
+use doors::testing::channel;
+
+#[doors::server_procedure]
+fn echo(x: Request<'_>) -> Response<&[u8]> {
+    Response::new(x.data)
+}
+
+#[test]
+fn batches_answer_each_request_in_order() {
+    let (_door, client) = channel(echo).unwrap();
+    let requests: [&[u8]; 3] = [b"one", b"two", b"three"];
+    let results = client.call_batch(&requests);
+    assert_eq!(results.len(), 3);
+    for (request, result) in requests.iter().zip(results) {
+        assert_eq!(result.unwrap(), *request);
+    }
+}
+
+#[test]
+fn batches_survive_responses_which_overflow_the_buffer() {
+    let (_door, client) = channel(echo).unwrap();
+    let client = client.with_default_rbuf(16);
+    let large = vec![7; 64 * 1024];
+    let requests: [&[u8]; 3] = [b"small", &large, &large];
+    let results = client.call_batch(&requests);
+    let results: Vec<_> = results.into_iter().map(Result::unwrap).collect();
+    assert_eq!(results[0], "small");
+    assert!(results[1].rbuf_is_mapped());
+    assert_eq!(results[1], &large[..]);
+    assert!(!results[2].rbuf_is_mapped());
+    assert_eq!(results[2], &large[..]);
+}
@@ -0,0 +1,60 @@
+use doors::illumos::DoorFd;
+use doors::server::{Request, Response};
+use doors::testing::channel;
+use std::fs::File;
+use std::io::Read;
+use std::os::fd::AsRawFd;
+use std::os::fd::IntoRawFd;
+
+#[doors::server_procedure]
+fn repeat(x: Request<'_>) -> Response<Vec<u8>> {
+    let times = x.descriptors.len().max(1);
+    let mut response = Response::new(x.data.repeat(times));
+    if x.descriptors.is_empty() {
+        let null = File::open("/dev/null").unwrap();
+        return response.add_descriptor(null.into_raw_fd(), true);
+    }
+
+    // Send back what we were sent, closing our copies on the way out.
+    for d in x.descriptors {
+        let fd = unsafe { d.d_data.d_desc.d_descriptor };
+        response = response.add_descriptor(fd, true);
+    }
+    response
+}
+
+#[test]
+fn data_is_copied_out() {
+    let (_door, client) = channel(repeat).unwrap();
+    let (data, fds) = client.call_collect(b"once", &[]).unwrap();
+    assert_eq!(data, b"once");
+    assert_eq!(fds.len(), 1);
+}
+
+#[test]
+fn descriptors_go_both_ways() {
+    let (_door, client) = channel(repeat).unwrap();
+    let passwd = File::open("/etc/passwd").unwrap();
+    let sent = [
+        DoorFd::new(passwd.as_raw_fd(), false),
+        DoorFd::new(passwd.as_raw_fd(), false),
+    ];
+    let (data, fds) = client.call_collect(b"ab", &sent).unwrap();
+    assert_eq!(data, b"abab");
+    assert_eq!(fds.len(), 2);
+
+    let mut passwd = File::from(fds.into_iter().next().unwrap());
+    let mut text = String::new();
+    passwd.read_to_string(&mut text).unwrap();
+    assert!(text.contains("root"));
+}
+
+#[test]
+fn mapped_responses_are_copied_and_unmapped() {
+    let (_door, client) = channel(repeat).unwrap();
+    let client = client.with_default_rbuf(16);
+    let large = vec![7; 100_000];
+    let (data, fds) = client.call_collect(&large, &[]).unwrap();
+    assert_eq!(data, large);
+    assert_eq!(fds.len(), 1);
+}
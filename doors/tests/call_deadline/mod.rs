@@ -0,0 +1,43 @@
+use doors::server::{Door, Request, Response};
+use doors::testing::TempDoor;
+use doors::DoorArgument;
+use doors::DoorCallError;
+use std::time::{Duration, Instant};
+
+#[doors::server_procedure]
+fn slow_double(x: Request<'_>) -> Response<[u8; 1]> {
+    std::thread::sleep(Duration::from_millis(x.data[1] as u64 * 10));
+    Response::new([x.data[0] * 2])
+}
+
+#[test]
+fn prompt_answers_land_in_our_buffer() {
+    let door = TempDoor::new(Door::create(slow_double).unwrap()).unwrap();
+    let client = door.client().unwrap();
+
+    let mut rbuf = [0; 16];
+    let arg = DoorArgument::new(&[21, 0], &[], &mut rbuf);
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let response = client.call_deadline(arg, deadline).unwrap();
+    assert_eq!(response, [42]);
+    assert!(!response.rbuf_is_mapped());
+    assert_eq!(response.rbuf().as_ptr(), rbuf.as_ptr());
+}
+
+#[test]
+fn slow_answers_time_out() {
+    let door = TempDoor::new(Door::create(slow_double).unwrap()).unwrap();
+    let client = door.client().unwrap();
+
+    let mut rbuf = [0; 16];
+    let arg = DoorArgument::new(&[21, 50], &[], &mut rbuf);
+    let deadline = Instant::now() + Duration::from_millis(50);
+    match client.call_deadline(arg, deadline) {
+        Err(e) => assert_eq!(e, DoorCallError::TimedOut),
+        Ok(_) => panic!("the call should have timed out"),
+    }
+
+    // The client is still perfectly usable.
+    let response = client.call_with_data(&[2, 0]).unwrap();
+    assert_eq!(response, [4]);
+}
@@ -0,0 +1,49 @@
+use doors::illumos::DoorFd;
+use doors::server::{Door, Request, Response};
+use doors::testing::channel;
+use std::os::fd::AsRawFd;
+
+/// Ask every door the client sent along to shout the request back.
+#[doors::server_procedure]
+fn relay(x: Request<'_>) -> Response<Vec<u8>> {
+    let mut replies = vec![];
+    for callback in x.incoming_doors() {
+        match callback {
+            Ok(callback) => {
+                let reply = callback.call_with_data(x.data).unwrap();
+                replies.extend_from_slice(reply.data());
+            }
+            Err(_) => replies.push(b'?'),
+        }
+    }
+    Response::new(replies)
+}
+
+#[doors::server_procedure]
+fn shout(x: Request<'_>) -> Response<Vec<u8>> {
+    Response::new(x.data.to_ascii_uppercase())
+}
+
+#[test]
+fn servers_can_call_back_into_clients() {
+    let (_door, relay) = channel(relay).unwrap();
+    let callback = Door::create(shout).unwrap();
+
+    let fds = [DoorFd::new(callback.as_raw_fd(), false)];
+    let mut rbuf = [0; 16];
+    let arg = doors::DoorArgument::new(b"hello", &fds, &mut rbuf);
+    let response = relay.call(arg).unwrap();
+    assert_eq!(response, b"HELLO");
+}
+
+#[test]
+fn other_descriptors_are_not_doors() {
+    let (_door, relay) = channel(relay).unwrap();
+    let file = std::fs::File::open("/dev/null").unwrap();
+
+    let fds = [DoorFd::new(file.as_raw_fd(), false)];
+    let mut rbuf = [0; 16];
+    let arg = doors::DoorArgument::new(b"hello", &fds, &mut rbuf);
+    let response = relay.call(arg).unwrap();
+    assert_eq!(response, b"?");
+}
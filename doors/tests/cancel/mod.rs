@@ -0,0 +1,62 @@
+use doors::illumos::DoorAttributes;
+use doors::server::{Door, Request, Response};
+use doors::testing::channel;
+use doors::{Client, DoorCallError};
+use std::os::fd::{AsRawFd, FromRawFd};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+static RELEASED: AtomicBool = AtomicBool::new(false);
+static UNREFERENCED: AtomicBool = AtomicBool::new(false);
+
+/// Holds on to every call until RELEASED is set.
+#[doors::server_procedure(unref)]
+fn stall(x: Request<'_>) -> Response<[u8; 0]> {
+    if x.is_unref() {
+        UNREFERENCED.store(true, Ordering::SeqCst);
+        return Response::empty();
+    }
+    while !RELEASED.load(Ordering::SeqCst) {
+        thread::sleep(Duration::from_millis(10));
+    }
+    Response::empty()
+}
+
+#[doors::server_procedure]
+fn echo(x: Request<'_>) -> Response<&[u8]> {
+    Response::new(x.data)
+}
+
+#[test]
+fn uncancelled_calls_finish() {
+    let (_door, client) = channel(echo).unwrap();
+    let (pending, _cancel) = client.call_cancellable(b"finished").unwrap();
+    let (data, fds) = pending.wait().unwrap();
+    assert_eq!(data, b"finished");
+    assert!(fds.is_empty());
+}
+
+#[test]
+fn cancelled_calls_let_go_of_the_door() {
+    let door =
+        Door::create_with_attributes(stall, DoorAttributes::unref()).unwrap();
+    let fd = unsafe { libc::dup(door.as_raw_fd()) };
+    let client = unsafe { Client::from_raw_fd(fd) };
+
+    let (pending, cancel) = client.call_cancellable(b"stall").unwrap();
+    drop(client);
+    thread::sleep(Duration::from_millis(100));
+
+    cancel.cancel();
+    assert_eq!(pending.wait().unwrap_err(), DoorCallError::Cancelled);
+    assert!(!UNREFERENCED.load(Ordering::SeqCst));
+
+    // Once the handler finishes, the door has no clients left.
+    RELEASED.store(true, Ordering::SeqCst);
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !UNREFERENCED.load(Ordering::SeqCst) {
+        assert!(Instant::now() < deadline, "no unref notification");
+        thread::sleep(Duration::from_millis(10));
+    }
+}
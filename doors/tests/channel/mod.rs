@@ -0,0 +1,39 @@
+use doors::prelude::*;
+use doors::testing::channel;
+
+#[server_procedure]
+fn echo(x: Request<'_>) -> Response<&[u8]> {
+    Response::new(x.data)
+}
+
+#[test]
+fn channels_need_no_filesystem() {
+    let (_door, echo) = channel(echo).unwrap();
+    let response = echo.call_with_data(b"hello").unwrap();
+    assert_eq!(response, b"hello");
+}
+
+#[test]
+fn channels_close_with_their_door() {
+    let (door, echo) = channel(echo).unwrap();
+    drop(door);
+    let result = echo.call_with_data(b"anyone there?");
+    assert_eq!(result.unwrap_err(), DoorCallError::Revoked);
+}
+
+/// Echo `text` through `client`, the way code built on `io::Result` would.
+fn echo_io(client: &Client, text: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut rbuf = [0; 64];
+    let response = client.call_io(DoorArgument::new(text, &[], &mut rbuf))?;
+    Ok(response.data().to_vec())
+}
+
+#[test]
+fn calls_compose_with_io_results() {
+    let (door, echo) = channel(echo).unwrap();
+    assert_eq!(echo_io(&echo, b"hello").unwrap(), b"hello");
+
+    drop(door);
+    let error = echo_io(&echo, b"anyone there?").unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::NotConnected);
+}
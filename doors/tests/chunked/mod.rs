@@ -0,0 +1,66 @@
+use doors::illumos::door_h;
+use doors::server::{Accumulated, Accumulator, Door, Request, Response};
+use doors::testing::TempDoor;
+use std::os::fd::AsRawFd;
+
+/// Answer with the length and the sum of the whole payload.
+#[doors::server_procedure(state = Accumulator)]
+fn summarize(x: Request<'_, Accumulator>) -> Response<Vec<u8>> {
+    match x.accumulate(x.state) {
+        Accumulated::Complete(payload) => {
+            let sum: u64 = payload.iter().map(|&b| b as u64).sum();
+            let mut summary = (payload.len() as u64).to_le_bytes().to_vec();
+            summary.extend_from_slice(&sum.to_le_bytes());
+            Response::new(summary)
+        }
+        Accumulated::Partial => Response::new(vec![]),
+        Accumulated::Invalid => Response::err_bytes(1, b"bad chunk"),
+    }
+}
+
+fn small_door() -> (TempDoor, &'static Accumulator) {
+    let accumulator: &'static Accumulator = Box::leak(Box::default());
+    let door = Door::create_with_state(summarize, accumulator).unwrap();
+    let rc = unsafe {
+        door_h::door_setparam(door.as_raw_fd(), door_h::DOOR_PARAM_DATA_MAX, 64)
+    };
+    assert_eq!(rc, 0);
+    (TempDoor::new(door).unwrap(), accumulator)
+}
+
+#[test]
+fn large_payloads_arrive_whole() {
+    let (door, accumulator) = small_door();
+    let client = door.client().unwrap();
+    assert_eq!(client.params().unwrap().data_max, 64);
+
+    let payload: Vec<u8> = (0..1000).map(|i| i as u8).collect();
+    let sum: u64 = payload.iter().map(|&b| b as u64).sum();
+    let response = client.call_chunked(&payload).unwrap();
+
+    let summary = response.as_result().unwrap();
+    assert_eq!(summary[..8], 1000u64.to_le_bytes());
+    assert_eq!(summary[8..], sum.to_le_bytes());
+    assert_eq!(accumulator.in_progress(), 0);
+}
+
+#[test]
+fn small_and_empty_payloads_are_one_chunk() {
+    let (door, _) = small_door();
+    let client = door.client().unwrap();
+
+    for payload in [&b""[..], &b"tiny"[..]] {
+        let response = client.call_chunked(payload).unwrap();
+        let summary = response.as_result().unwrap();
+        assert_eq!(summary[..8], (payload.len() as u64).to_le_bytes());
+    }
+}
+
+#[test]
+fn unframed_requests_are_invalid() {
+    let (door, _) = small_door();
+    let client = door.client().unwrap();
+
+    let response = client.call_with_data(b"not a chunk").unwrap();
+    assert_eq!(response.as_result().unwrap_err().code, 1);
+}
@@ -0,0 +1,34 @@
+use doors::illumos::door_info;
+use doors::server::{Request, Response};
+use doors::testing::channel;
+use std::os::fd::{AsRawFd, FromRawFd, IntoRawFd, OwnedFd};
+
+#[doors::server_procedure]
+fn nothing(_x: Request<'_>) -> Response<[u8; 0]> {
+    Response::empty()
+}
+
+// Other tests open descriptors all the time, so a closed descriptor's number
+// may well have been handed out again by the time we look. Door ids are never
+// reused, though, so whatever the number refers to now, it must not be our
+// door.
+
+#[test]
+fn dropped_clients_close_their_descriptors() {
+    let (_door, client) = channel(nothing).unwrap();
+    let fd = client.as_raw_fd();
+    let id = door_info(fd).unwrap().id();
+
+    drop(client);
+    assert!(!door_info(fd).is_ok_and(|info| info.id() == id));
+}
+
+#[test]
+fn clients_given_up_as_raw_descriptors_stay_open() {
+    let (_door, client) = channel(nothing).unwrap();
+    let id = door_info(client.as_raw_fd()).unwrap().id();
+
+    let fd = client.into_raw_fd();
+    assert_eq!(door_info(fd).unwrap().id(), id);
+    drop(unsafe { OwnedFd::from_raw_fd(fd) });
+}
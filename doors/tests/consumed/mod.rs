@@ -0,0 +1,38 @@
+use doors::server::{Request, Response};
+use doors::testing::channel;
+
+/// Sum up every complete line of the request, and say how much that was.
+#[doors::server_procedure]
+fn count_lines(x: Request<'_>) -> Response<Vec<u8>> {
+    let consumed = match x.data.iter().rposition(|&b| b == b'\n') {
+        Some(last) => last + 1,
+        None => 0,
+    };
+    let lines = x.data[..consumed].iter().filter(|&&b| b == b'\n').count();
+    Response::with_consumed(&(lines as u32).to_le_bytes(), consumed)
+}
+
+#[doors::server_procedure]
+fn plain(_x: Request<'_>) -> Response<&'static [u8]> {
+    Response::new(b"no count here")
+}
+
+#[test]
+fn servers_report_partial_consumption() {
+    let (_door, client) = channel(count_lines).unwrap();
+    let response = client.call_with_data(b"one\ntwo\nthr").unwrap();
+    let (consumed, rest) = response.consumed().unwrap();
+    assert_eq!(consumed, 8);
+    assert_eq!(rest, 2u32.to_le_bytes());
+
+    // The client sends what was left over, along with more input.
+    let response = client.call_with_data(b"thr\nfour\n").unwrap();
+    assert_eq!(response.consumed().unwrap().0, 9);
+}
+
+#[test]
+fn ordinary_responses_report_nothing() {
+    let (_door, client) = channel(plain).unwrap();
+    let response = client.call_with_data(&[]).unwrap();
+    assert!(response.consumed().is_none());
+}
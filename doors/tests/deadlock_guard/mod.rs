@@ -0,0 +1,139 @@
+use doors::illumos::door_h::door_desc_t;
+use doors::server::{Door, Invocation, Request, Response};
+use doors::testing::TempDoor;
+use doors::Client;
+use doors::DoorArgument;
+use doors::DoorCallError;
+use std::sync::{Barrier, OnceLock};
+use std::thread;
+
+static ELSEWHERE: OnceLock<Client> = OnceLock::new();
+static ITSELF: OnceLock<Client> = OnceLock::new();
+static ITSELF_TIMED: OnceLock<Client> = OnceLock::new();
+static POOLED: [OnceLock<Client>; 2] = [OnceLock::new(), OnceLock::new()];
+static BOTH_FINISHING: [Barrier; 2] = [Barrier::new(2), Barrier::new(2)];
+
+fn relay(target: &Client, data: &[u8]) -> Response<&'static [u8]> {
+    verdict(target.call_with_data(data))
+}
+
+fn verdict<T>(result: Result<T, DoorCallError>) -> Response<&'static [u8]> {
+    match result {
+        Ok(_) => Response::new(b"answered"),
+        Err(DoorCallError::WouldDeadlock) => Response::new(b"refused"),
+        Err(_) => Response::new(b"failed"),
+    }
+}
+
+#[doors::server_procedure]
+fn echo(x: Request<'_>) -> Response<Vec<u8>> {
+    Response::new(x.data.to_vec())
+}
+
+#[doors::server_procedure]
+fn call_elsewhere(x: Request<'_>) -> Response<&'static [u8]> {
+    relay(ELSEWHERE.get().unwrap(), x.data)
+}
+
+#[doors::server_procedure]
+fn call_itself(x: Request<'_>) -> Response<&'static [u8]> {
+    relay(ITSELF.get().unwrap(), x.data)
+}
+
+#[doors::server_procedure]
+fn call_itself_timed(x: Request<'_>) -> Response<&'static [u8]> {
+    let arg = DoorArgument::new(x.data, &[], &mut []);
+    verdict(ITSELF_TIMED.get().unwrap().call_timed(arg))
+}
+
+/// Served by two threads bound to its door. Asked to "finish", it enters an
+/// invocation, waits until both threads are doing so, and then closes it,
+/// with `Request::reply` if `REPLY` is set, or else `Invocation::exit`. Asked
+/// to "call", it calls its own door without entering an invocation, as a
+/// hand-written procedure would.
+extern "C" fn pooled<const REPLY: bool>(
+    cookie: *const libc::c_void,
+    argp: *const libc::c_char,
+    arg_size: libc::size_t,
+    _dp: *const door_desc_t,
+    _n_desc: libc::c_uint,
+) {
+    let data =
+        unsafe { std::slice::from_raw_parts(argp as *const u8, arg_size) };
+    let request = Request {
+        cookie: cookie as u64,
+        data,
+        descriptors: &[],
+        state: &(),
+    };
+    match data {
+        b"finish" => {
+            let invocation = Invocation::enter("pooled", &request);
+            BOTH_FINISHING[REPLY as usize].wait();
+            if REPLY {
+                request.reply(b"finished")
+            }
+            invocation.exit();
+            Response::new(b"finished").door_return()
+        }
+        b"call" => {
+            let itself = POOLED[REPLY as usize].get().unwrap();
+            relay(itself, b"noop").door_return()
+        }
+        _ => Response::new(data).door_return(),
+    }
+}
+
+/// Have both of `door`'s threads finish an invocation, and then have
+/// whichever takes the next call make a call to `door`.
+fn finish_then_call(door: &Door, itself: &OnceLock<Client>) -> DoorArgument {
+    assert!(itself.set(door.loopback_client().unwrap()).is_ok());
+    thread::scope(|s| {
+        for _ in 0..2 {
+            s.spawn(|| {
+                let client = door.loopback_client().unwrap();
+                let response = client.call_with_data(b"finish").unwrap();
+                assert_eq!(response, "finished");
+            });
+        }
+    });
+    let client = door.loopback_client().unwrap();
+    client.call_with_data(b"call").unwrap()
+}
+
+#[test]
+fn handlers_may_call_other_doors() {
+    let echo = TempDoor::new(Door::create(echo).unwrap()).unwrap();
+    assert!(ELSEWHERE.set(echo.client().unwrap()).is_ok());
+    let door = TempDoor::new(Door::create(call_elsewhere).unwrap()).unwrap();
+    let client = door.client().unwrap();
+    assert_eq!(client.call_with_data(b"hi").unwrap(), "answered");
+}
+
+#[test]
+fn handlers_may_not_call_their_own_door() {
+    let door = TempDoor::new(Door::create(call_itself).unwrap()).unwrap();
+    assert!(ITSELF.set(door.client().unwrap()).is_ok());
+    let client = door.client().unwrap();
+    assert_eq!(client.call_with_data(b"hi").unwrap(), "refused");
+}
+
+#[test]
+fn handlers_may_not_time_calls_to_their_own_door() {
+    let door = TempDoor::new(Door::create(call_itself_timed).unwrap()).unwrap();
+    assert!(ITSELF_TIMED.set(door.client().unwrap()).is_ok());
+    let client = door.client().unwrap();
+    assert_eq!(client.call_with_data(b"hi").unwrap(), "refused");
+}
+
+#[test]
+fn the_guard_is_lifted_between_invocations() {
+    let door = Door::create_private(pooled::<false>, 2).unwrap();
+    assert_eq!(finish_then_call(&door, &POOLED[0]), "answered");
+}
+
+#[test]
+fn replies_lift_the_guard() {
+    let door = Door::create_private(pooled::<true>, 2).unwrap();
+    assert_eq!(finish_then_call(&door, &POOLED[1]), "answered");
+}
@@ -0,0 +1,35 @@
+use doors::server::{Request, Response};
+use doors::testing::channel;
+
+#[doors::server_procedure]
+fn echo(x: Request<'_>) -> Response<&[u8]> {
+    Response::new(x.data)
+}
+
+#[test]
+fn text_responses_decode() {
+    let (_door, echo) = channel(echo).unwrap();
+
+    let response = echo.call_with_data(b"hello\0").unwrap();
+    assert_eq!(response.as_bytes(), b"hello\0");
+    assert_eq!(response.as_cstr().unwrap().to_str(), Ok("hello"));
+    assert_eq!(response.as_str(), Ok("hello\0"));
+
+    let response = echo.call_with_data("h\u{e9}llo".as_bytes()).unwrap();
+    assert_eq!(response.as_str(), Ok("h\u{e9}llo"));
+}
+
+#[test]
+fn malformed_text_is_an_error() {
+    let (_door, echo) = channel(echo).unwrap();
+
+    let response = echo.call_with_data(b"no terminator").unwrap();
+    assert!(response.as_cstr().is_err());
+
+    let response = echo.call_with_data(b"two\0nuls\0").unwrap();
+    assert!(response.as_cstr().is_err());
+
+    let response = echo.call_with_data(&[0xff, 0xfe, 0]).unwrap();
+    assert!(response.as_str().is_err());
+    assert!(response.as_cstr().unwrap().to_str().is_err());
+}
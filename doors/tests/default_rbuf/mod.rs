@@ -0,0 +1,53 @@
+use doors::illumos::door_h;
+use doors::server::{Door, Request, Response};
+use doors::testing::TempDoor;
+use doors::DEFAULT_RBUF_MAX;
+use std::os::fd::AsRawFd;
+
+#[doors::server_procedure]
+fn echo(x: Request<'_>) -> Response<&[u8]> {
+    Response::new(x.data)
+}
+
+fn echo_door(data_max: Option<usize>) -> TempDoor {
+    let door = Door::create(echo).unwrap();
+    if let Some(data_max) = data_max {
+        let fd = door.as_raw_fd();
+        let param = door_h::DOOR_PARAM_DATA_MAX;
+        assert_eq!(unsafe { door_h::door_setparam(fd, param, data_max) }, 0);
+    }
+    TempDoor::new(door).unwrap()
+}
+
+#[test]
+fn small_responses_are_not_mapped() {
+    let door = echo_door(None);
+    let client = door.client().unwrap();
+    assert_eq!(client.default_rbuf(), DEFAULT_RBUF_MAX);
+
+    let response = client.call_with_data(b"small").unwrap();
+    assert_eq!(response, b"small");
+    assert!(!response.rbuf_is_mapped());
+
+    let large = vec![1; DEFAULT_RBUF_MAX + 1];
+    let response = client.call_with_data(&large).unwrap();
+    assert_eq!(response.data(), &large[..]);
+    assert!(response.rbuf_is_mapped());
+}
+
+#[test]
+fn rbufs_follow_the_data_limit() {
+    let door = echo_door(Some(400));
+    let client = door.client().unwrap();
+    assert_eq!(client.default_rbuf(), 100);
+}
+
+#[test]
+fn rbufs_can_be_overridden() {
+    let door = echo_door(None);
+    let client = door.client().unwrap().with_default_rbuf(0);
+    assert_eq!(client.default_rbuf(), 0);
+
+    let response = client.call_with_data(b"small").unwrap();
+    assert!(response.rbuf_is_mapped());
+}
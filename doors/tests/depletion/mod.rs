@@ -0,0 +1,56 @@
+use doors::server::{Door, Request, Response};
+use doors::testing::TempDoor;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[doors::server_procedure]
+fn slow(_x: Request<'_>) -> Response<[u8; 0]> {
+    std::thread::sleep(Duration::from_millis(100));
+    Response::empty()
+}
+
+#[test]
+fn busy_pools_ask_for_more_threads() {
+    let depletions = Arc::new(AtomicUsize::new(0));
+    let counter = depletions.clone();
+    let door = Door::builder()
+        .private_pool(1)
+        .on_depletion(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+            true
+        })
+        .build(slow)
+        .unwrap();
+    let door = TempDoor::new(door).unwrap();
+
+    let callers: Vec<_> = (0..3)
+        .map(|_| {
+            let client = door.client().unwrap();
+            std::thread::spawn(move || {
+                client.call_with_data(&[]).unwrap();
+            })
+        })
+        .collect();
+    for caller in callers {
+        caller.join().unwrap();
+    }
+    assert!(depletions.load(Ordering::SeqCst) >= 1);
+}
+
+#[test]
+fn revoked_doors_let_go_of_their_callbacks() {
+    let depletions = Arc::new(AtomicUsize::new(0));
+    let counter = depletions.clone();
+    let door = Door::builder()
+        .on_depletion(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+            true
+        })
+        .build(slow)
+        .unwrap();
+    assert_eq!(Arc::strong_count(&depletions), 2);
+
+    drop(door);
+    assert_eq!(Arc::strong_count(&depletions), 1);
+}
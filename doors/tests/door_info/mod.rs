@@ -0,0 +1,41 @@
+use doors::illumos::door_info;
+use doors::server::{Door, Request, Response};
+use doors::testing::TempDoor;
+use std::os::fd::AsRawFd;
+
+#[doors::server_procedure]
+fn ignore(_x: Request<'_>) -> Response<[u8; 0]> {
+    Response::empty()
+}
+
+#[test]
+fn clients_find_the_door_they_expect() {
+    let door = Door::create_with_cookie(ignore, 1234).unwrap();
+    let door = TempDoor::new(door).unwrap();
+    let client = door.client().unwrap();
+
+    let info = door_info(client.as_raw_fd()).unwrap();
+    assert!(info.belongs_to_current_process());
+    assert!(!info.is_private());
+    info.assert_matches(std::process::id(), 1234);
+}
+
+#[test]
+#[should_panic(expected = "door has the wrong cookie")]
+fn assert_matches_notices_the_wrong_cookie() {
+    let door = Door::create_with_cookie(ignore, 1234).unwrap();
+    let info = door_info(door.as_raw_fd()).unwrap();
+    info.assert_matches(std::process::id(), 4321);
+}
+
+#[test]
+fn revoked_doors_say_so() {
+    let door = Door::create(ignore).unwrap();
+    let fd = unsafe { libc::dup(door.as_raw_fd()) };
+    assert_ne!(fd, -1);
+    assert!(!door_info(fd).unwrap().is_revoked());
+
+    drop(door);
+    assert!(door_info(fd).unwrap().is_revoked());
+    unsafe { libc::close(fd) };
+}
@@ -0,0 +1,34 @@
+use doors::illumos::DoorAttributes;
+use doors::server::{Door, Request, Response};
+use doors::testing::TempDoor;
+use doors::DoorParamError;
+
+#[doors::server_procedure]
+fn ignore(_x: Request<'_>) -> Response<[u8; 0]> {
+    Response::empty()
+}
+
+#[test]
+fn default_params_accept_anything() {
+    let door = TempDoor::new(Door::create(ignore).unwrap()).unwrap();
+    let params = door.client().unwrap().params().unwrap();
+    assert_eq!(params.data_max, usize::MAX);
+    assert_eq!(params.data_min, 0);
+    assert!(params.desc_max > 0);
+}
+
+#[test]
+fn refusing_descriptors_shows_in_params() {
+    let attrs = DoorAttributes::refuse_desc();
+    let door = Door::create_with_attributes(ignore, attrs).unwrap();
+    let door = TempDoor::new(door).unwrap();
+    let params = door.client().unwrap().params().unwrap();
+    assert_eq!(params.desc_max, 0);
+}
+
+#[test]
+fn unknown_params_are_invalid() {
+    let door = TempDoor::new(Door::create(ignore).unwrap()).unwrap();
+    let client = door.client().unwrap();
+    assert_eq!(client.getparam(-1), Err(DoorParamError::EINVAL));
+}
@@ -0,0 +1,24 @@
+use doors::Client;
+use doors::DoorArgument;
+
+const INCREMENT: u8 = 0;
+const FETCH: u8 = 1;
+
+#[test]
+fn doorserver_increments_shared_counter() {
+    let counter = Client::open("/tmp/doorserver_kv.door").unwrap();
+    let mut rbuf: [u8; 1] = [0];
+
+    let arg = DoorArgument::new(&[FETCH], &[], &mut rbuf);
+    counter.call(arg).unwrap();
+    let before = rbuf[0];
+
+    for _ in 0..3 {
+        let arg = DoorArgument::new(&[INCREMENT], &[], &mut rbuf);
+        counter.call(arg).unwrap();
+    }
+
+    let arg = DoorArgument::new(&[FETCH], &[], &mut rbuf);
+    counter.call(arg).unwrap();
+    assert_eq!(rbuf[0], before + 3);
+}
@@ -0,0 +1,35 @@
+use doors::server::{Door, Request, Response};
+use doors::testing::TempDoor;
+use doors::DoorError;
+
+const EMPTY_REQUEST: u32 = 22;
+
+#[doors::server_procedure]
+fn first_byte(x: Request<'_>) -> Response<Vec<u8>> {
+    match x.data.first() {
+        Some(&b) => Response::new(vec![b]),
+        None => Response::err_bytes(EMPTY_REQUEST, b"nothing to look at"),
+    }
+}
+
+#[test]
+fn ordinary_responses_are_ok() {
+    let door = TempDoor::new(Door::create(first_byte).unwrap()).unwrap();
+    let client = door.client().unwrap();
+
+    let response = client.call_with_data(b"xyz").unwrap();
+    assert_eq!(response.as_result(), Ok(&b"x"[..]));
+}
+
+#[test]
+fn error_frames_are_decoded() {
+    let door = TempDoor::new(Door::create(first_byte).unwrap()).unwrap();
+    let client = door.client().unwrap();
+
+    let response = client.call_with_data(&[]).unwrap();
+    let expected = DoorError {
+        code: EMPTY_REQUEST,
+        detail: b"nothing to look at".to_vec(),
+    };
+    assert_eq!(response.as_result(), Err(expected));
+}
@@ -0,0 +1,47 @@
+use doors::server::{Door, Request, Response};
+use doors::testing::TempDoor;
+
+#[doors::server_procedure]
+fn capitalize(x: Request<'_>) -> Response<Vec<u8>> {
+    Response::new(x.data.to_ascii_uppercase())
+}
+
+#[doors::server_procedure]
+fn expand(x: Request<'_>) -> Response<[u8; 8192]> {
+    Response::new([x.data[0]; 8192])
+}
+
+#[test]
+fn exchange_replaces_the_request_with_the_response() {
+    let door = TempDoor::new(Door::create(capitalize).unwrap()).unwrap();
+    let client = door.client().unwrap();
+
+    let mut buf = Vec::with_capacity(64);
+    buf.extend_from_slice(b"hello");
+    client.exchange(&mut buf).unwrap();
+    assert_eq!(buf, b"HELLO");
+
+    // The same buffer goes round again without needing to grow.
+    let capacity = buf.capacity();
+    buf.clear();
+    buf.extend_from_slice(b"world");
+    client.exchange(&mut buf).unwrap();
+    assert_eq!(buf, b"WORLD");
+    assert_eq!(buf.capacity(), capacity);
+}
+
+#[test]
+fn exchange_grows_for_large_responses() {
+    let door = TempDoor::new(Door::create(expand).unwrap()).unwrap();
+    let client = door.client().unwrap();
+
+    let mut buf = vec![7];
+    client.exchange(&mut buf).unwrap();
+    assert_eq!(buf, [7; 8192]);
+
+    // Now that it is big enough, the next response lands in place.
+    buf.truncate(1);
+    buf[0] = 9;
+    client.exchange(&mut buf).unwrap();
+    assert_eq!(buf, [9; 8192]);
+}
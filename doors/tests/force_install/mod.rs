@@ -0,0 +1,58 @@
+use doors::server::{Door, Request, Response};
+use doors::Client;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+#[doors::server_procedure]
+fn green(_x: Request<'_>) -> Response<[u8; 5]> {
+    Response::new(*b"green")
+}
+
+#[test]
+fn clients_never_find_the_path_without_a_door() {
+    let path = std::env::temp_dir().join("force_install_gapless.door");
+    let first = Door::create(green).unwrap();
+    first.force_install(&path).unwrap();
+
+    let done = Arc::new(AtomicBool::new(false));
+    let caller = {
+        let (path, done) = (path.clone(), done.clone());
+        thread::spawn(move || {
+            let mut calls = 0;
+            while !done.load(Ordering::SeqCst) {
+                let client = Client::open(&path).unwrap();
+                assert_eq!(client.call_with_data(&[]).unwrap(), "green");
+                calls += 1;
+            }
+            calls
+        })
+    };
+
+    // Every door is kept alive until the end, so that a client which opened
+    // one just before it was replaced can still call it.
+    let mut doors = vec![first];
+    for _ in 0..50 {
+        let door = Door::create(green).unwrap();
+        door.force_install(&path).unwrap();
+        doors.push(door);
+    }
+    done.store(true, Ordering::SeqCst);
+    assert!(caller.join().unwrap() > 0);
+
+    // Replaced doors leave no hidden jambs behind; only the current one.
+    let prefix = format!(".force_install_gapless.door.{}.", std::process::id());
+    let dir = std::fs::read_dir(std::env::temp_dir()).unwrap();
+    let jambs = dir
+        .filter_map(Result::ok)
+        .filter(|entry| {
+            let name = entry.file_name();
+            name.to_string_lossy().starts_with(&prefix)
+        })
+        .count();
+    assert_eq!(jambs, 1);
+
+    doors::illumos::fdetach(&path).ok();
+    std::fs::remove_file(std::fs::canonicalize(&path).unwrap()).ok();
+    std::fs::remove_file(&path).ok();
+}
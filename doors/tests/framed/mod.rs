@@ -0,0 +1,46 @@
+use doors::server::{Request, Response};
+use doors::testing::channel;
+use doors::DoorCallError;
+
+#[doors::server_procedure]
+fn framed_echo(x: Request<'_>) -> Response<Vec<u8>> {
+    Response::framed(x.data)
+}
+
+/// Claim a longer payload than is actually sent.
+#[doors::server_procedure]
+fn liar(x: Request<'_>) -> Response<Vec<u8>> {
+    let mut frame = 100u32.to_le_bytes().to_vec();
+    frame.extend_from_slice(x.data);
+    Response::new(frame)
+}
+
+#[doors::server_procedure]
+fn echo(x: Request<'_>) -> Response<&[u8]> {
+    Response::new(x.data)
+}
+
+#[test]
+fn framed_responses_are_unwrapped() {
+    let (_door, client) = channel(framed_echo).unwrap();
+    assert_eq!(client.call_framed(b"hello").unwrap(), b"hello");
+    assert_eq!(client.call_framed(&[]).unwrap(), b"");
+
+    let raw = client.call_with_data(b"hi").unwrap();
+    assert_eq!(raw, [2, 0, 0, 0, b'h', b'i']);
+}
+
+#[test]
+fn short_frames_are_refused() {
+    let (_door, client) = channel(liar).unwrap();
+    let result = client.call_framed(b"only a little");
+    assert_eq!(result, Err(DoorCallError::TruncatedFrame(104)));
+
+    let (_door, client) = channel(echo).unwrap();
+    let result = client.call_framed(&[1, 2]);
+    assert_eq!(result, Err(DoorCallError::TruncatedFrame(4)));
+
+    // Bytes beyond the declared payload are not part of it.
+    let result = client.call_framed(&[1, 0, 0, 0, 7, 8, 9]);
+    assert_eq!(result.unwrap(), [7]);
+}
@@ -0,0 +1,30 @@
+use doors::server::{Request, Response};
+use doors::testing::channel;
+use std::fs::File;
+
+const SMALL: &str = "/tmp/from_file_small.conf";
+const LARGE: &str = "/tmp/from_file_large.conf";
+
+#[doors::server_procedure]
+fn config(x: Request<'_>) -> Response<Vec<u8>> {
+    let path = std::str::from_utf8(x.data).unwrap();
+    let mut file = File::open(path).unwrap();
+    Response::from_file(&mut file, 16)
+        .unwrap_or_else(|e| Response::err_bytes(1, e.to_string().as_bytes()))
+}
+
+#[test]
+fn files_are_returned_as_data() {
+    std::fs::write(SMALL, "verbose = true\n").unwrap();
+    let (_door, config) = channel(config).unwrap();
+    let response = config.call_with_data(SMALL.as_bytes()).unwrap();
+    assert_eq!(response, "verbose = true\n");
+}
+
+#[test]
+fn files_over_the_limit_are_refused() {
+    std::fs::write(LARGE, "verbose = true\ncolor = never\n").unwrap();
+    let (_door, config) = channel(config).unwrap();
+    let response = config.call_with_data(LARGE.as_bytes()).unwrap();
+    assert_eq!(response.as_result().unwrap_err().code, 1);
+}
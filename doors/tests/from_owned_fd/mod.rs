@@ -0,0 +1,32 @@
+use doors::server::{Door, Request, Response};
+use doors::testing::TempDoor;
+use doors::Client;
+use std::fs::File;
+use std::io;
+use std::os::fd::OwnedFd;
+
+#[doors::server_procedure]
+fn echo(x: Request<'_>) -> Response<&[u8]> {
+    Response::new(x.data)
+}
+
+#[test]
+fn owned_doors_become_clients() {
+    let door = TempDoor::new(Door::create(echo).unwrap()).unwrap();
+    let fd = OwnedFd::from(File::open(door.path()).unwrap());
+    let client = Client::from_owned_fd(fd).unwrap();
+    assert_eq!(client.call_with_data(b"owned").unwrap(), "owned");
+}
+
+#[test]
+fn other_descriptors_are_handed_back() {
+    let fd = OwnedFd::from(File::open("/dev/null").unwrap());
+    let (fd, error) = match Client::from_owned_fd(fd) {
+        Ok(_) => panic!("/dev/null is not a door"),
+        Err(e) => e,
+    };
+    assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+
+    // The descriptor is still open, and still ours.
+    assert!(File::from(fd).metadata().is_ok());
+}
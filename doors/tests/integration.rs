@@ -1,7 +1,65 @@
+pub mod ambient_deadline;
+pub mod arc_state;
+pub mod bad_descriptors;
 pub mod barebones_capitalize;
 pub mod barebones_open;
+pub mod call_batch;
+pub mod call_collect;
+pub mod call_deadline;
+pub mod callbacks;
+pub mod cancel;
 pub mod capitalize_door_response;
+pub mod channel;
+pub mod chunked;
+pub mod client_drop;
+pub mod consumed;
+pub mod deadlock_guard;
+pub mod decoding;
+pub mod default_rbuf;
+pub mod depletion;
+pub mod door_info;
+pub mod door_params;
+pub mod doorserver_kv;
+pub mod error_frames;
+pub mod exchange;
+pub mod force_install;
+pub mod framed;
+pub mod from_file;
+pub mod from_owned_fd;
+pub mod max_response;
 pub mod mmap;
+pub mod notify;
+pub mod owned_argument;
+pub mod priority;
+pub mod procmac_authorize;
+pub mod procmac_config;
+pub mod procmac_cow;
+pub mod procmac_directory;
 pub mod procmac_double;
+pub mod procmac_install;
 pub mod procmac_kv;
+pub mod procmac_nested;
 pub mod procmac_open;
+pub mod procmac_stash;
+pub mod procmac_state;
+pub mod procmac_typed;
+pub mod procmac_unref;
+pub mod proto;
+pub mod refuse_desc;
+pub mod reinstall;
+pub mod reply;
+pub mod resilient;
+pub mod response_buffer;
+pub mod response_builder;
+pub mod response_pool;
+pub mod roundtrip;
+pub mod runtime;
+pub mod scalars;
+pub mod server_panics;
+pub mod single_descriptor;
+pub mod stack_size;
+pub mod suspend;
+pub mod ucred;
+pub mod wait_revoked;
+pub mod with_files;
+pub mod zeroize;
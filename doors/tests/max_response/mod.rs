@@ -0,0 +1,34 @@
+use doors::server::{Door, Request, Response};
+use doors::testing::TempDoor;
+use doors::DoorCallError;
+
+#[doors::server_procedure]
+fn echo(x: Request<'_>) -> Response<&[u8]> {
+    Response::new(x.data)
+}
+
+#[test]
+fn large_mapped_responses_are_refused() {
+    let door = TempDoor::new(Door::create(echo).unwrap()).unwrap();
+    let client = door.client().unwrap().with_default_rbuf(0);
+    let client = client.with_max_response(8192);
+
+    let response = client.call_with_data(&[1; 100]).unwrap();
+    assert_eq!(response.data(), &[1; 100][..]);
+
+    match client.call_with_data(&[1; 65536]) {
+        Err(DoorCallError::ResponseTooLarge(size)) => assert!(size > 8192),
+        other => panic!("expected ResponseTooLarge, got {:?}", other.err()),
+    }
+}
+
+#[test]
+fn responses_in_our_own_buffer_are_not_refused() {
+    let door = TempDoor::new(Door::create(echo).unwrap()).unwrap();
+    let client = door.client().unwrap().with_default_rbuf(1024);
+    let client = client.with_max_response(0);
+
+    let response = client.call_with_data(b"fits").unwrap();
+    assert_eq!(response, b"fits");
+    assert!(!response.rbuf_is_mapped());
+}
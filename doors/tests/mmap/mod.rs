@@ -64,3 +64,94 @@ fn borrowed_rbuf_is_expected_length() {
     let response = no_junk.call(arg).unwrap();
     assert_eq!(response.rbuf().len(), 1);
 }
+
+#[test]
+fn rbuf_is_mapped_tracks_ownership() {
+    let junk = Client::open("/tmp/junk.door").unwrap();
+    let no_junk = Client::open("/tmp/no_junk.door").unwrap();
+
+    let mut rbuf: [u8; 1] = [0];
+    let arg = DoorArgument::new(&[111], &[], &mut rbuf);
+    assert!(!arg.rbuf_is_mapped());
+    let response = junk.call(arg).unwrap();
+    assert!(response.rbuf_is_mapped());
+
+    let mut rbuf: [u8; 16] = [0; 16];
+    let arg = DoorArgument::new(&[111], &[], &mut rbuf);
+    let response = no_junk.call(arg).unwrap();
+    assert!(!response.rbuf_is_mapped());
+    assert_eq!(response.rbuf().len(), 16);
+    assert_eq!(response.data().len(), 1);
+}
+
+#[test]
+fn ensure_rbuf_capacity_avoids_the_mapping() {
+    let junk = Client::open("/tmp/junk.door").unwrap();
+
+    let mut rbuf: [u8; 1] = [0];
+    let mut arg = DoorArgument::new(&[111], &[], &mut rbuf);
+    arg.ensure_rbuf_capacity(8192);
+    assert_eq!(arg.rbuf().len(), 8192);
+
+    let response = junk.call(arg).unwrap();
+    assert!(!response.rbuf_is_mapped());
+    assert_eq!(response.rbuf().len(), 8192);
+}
+
+#[test]
+fn ensure_rbuf_capacity_leaves_mapped_buffers_alone() {
+    let junk = Client::open("/tmp/junk.door").unwrap();
+
+    let mut rbuf: [u8; 1] = [0];
+    let arg = DoorArgument::new(&[111], &[], &mut rbuf);
+    let mut response = junk.call(arg).unwrap();
+    let data = response.data().to_vec();
+
+    response.ensure_rbuf_capacity(1 << 20);
+    assert!(response.rbuf_is_mapped());
+    assert_eq!(response.rbuf().len(), 4096);
+    assert_eq!(response.data(), &data[..]);
+}
+
+#[test]
+fn door_arg_t_shows_the_mapped_buffer() {
+    let junk = Client::open("/tmp/junk.door").unwrap();
+
+    let mut rbuf: [u8; 1] = [0];
+    let arg = DoorArgument::new(&[111], &[], &mut rbuf);
+    assert_eq!(arg.as_door_arg_t().rbuf as *const u8, rbuf.as_ptr());
+    assert_eq!(arg.as_door_arg_t().rsize, 1);
+
+    let response = junk.call(arg).unwrap();
+    let raw = response.as_door_arg_t();
+    assert_ne!(raw.rbuf as *const u8, rbuf.as_ptr());
+    assert_eq!(raw.rsize, response.rbuf().len());
+    assert_eq!(raw.data_size, response.data().len());
+}
+
+#[test]
+fn raw_mappings_are_left_to_the_caller() {
+    let junk = Client::open("/tmp/junk.door").unwrap();
+
+    let mut rbuf: [u8; 1] = [0];
+    let arg = DoorArgument::new(&[111], &[], &mut rbuf);
+    let response = junk.call(arg).unwrap();
+    let data = response.data().as_ptr();
+
+    let (ptr, len) = response.into_raw_mapping().unwrap();
+    assert_eq!(len, 4096);
+    assert!(
+        (ptr as *const u8..ptr.wrapping_add(len) as *const u8).contains(&data)
+    );
+    assert_eq!(unsafe { libc::munmap(ptr as *mut libc::c_void, len) }, 0);
+}
+
+#[test]
+fn borrowed_rbufs_have_no_raw_mapping() {
+    let no_junk = Client::open("/tmp/no_junk.door").unwrap();
+
+    let mut rbuf: [u8; 1] = [0];
+    let arg = DoorArgument::new(&[111], &[], &mut rbuf);
+    let response = no_junk.call(arg).unwrap();
+    assert!(response.into_raw_mapping().is_none());
+}
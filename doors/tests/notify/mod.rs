@@ -0,0 +1,33 @@
+use doors::server::{Request, Response};
+use doors::testing::channel;
+use std::fs::File;
+use std::os::fd::IntoRawFd;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static COUNT: AtomicUsize = AtomicUsize::new(0);
+
+#[doors::server_procedure]
+fn increment(_x: Request<'_>) -> Response<[u8; 0]> {
+    COUNT.fetch_add(1, Ordering::SeqCst);
+    Response::empty()
+}
+
+#[doors::server_procedure]
+fn chatty(_x: Request<'_>) -> Response<Vec<u8>> {
+    let null = File::open("/dev/null").unwrap();
+    Response::new(vec![1; 100_000]).add_descriptor(null.into_raw_fd(), true)
+}
+
+#[test]
+fn notifications_reach_the_server() {
+    let (_door, client) = channel(increment).unwrap();
+    client.notify(&[]).unwrap();
+    client.notify(b"again").unwrap();
+    assert_eq!(COUNT.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn unwanted_replies_are_discarded() {
+    let (_door, client) = channel(chatty).unwrap();
+    client.notify(&[]).unwrap();
+}
@@ -0,0 +1,34 @@
+use doors::server::{Request, Response};
+use doors::testing::channel;
+use doors::OwnedDoorArgument;
+
+#[doors::server_procedure]
+fn shout(x: Request<'_>) -> Response<Vec<u8>> {
+    Response::new(x.data.to_ascii_uppercase())
+}
+
+/// Builds a request whose buffers would otherwise die with this function.
+fn greeting(name: &str) -> OwnedDoorArgument {
+    let data = format!("hello, {}", name).into_bytes();
+    OwnedDoorArgument::new(data, 64)
+}
+
+#[test]
+fn requests_can_be_built_elsewhere() {
+    let (_door, shout) = channel(shout).unwrap();
+    let arg = greeting("world");
+    assert_eq!(arg.data(), b"hello, world");
+
+    let response = shout.call_owned_arg(arg).unwrap();
+    assert_eq!(response.data(), b"HELLO, WORLD");
+    assert!(response.argument().descriptors().is_empty());
+}
+
+#[test]
+fn large_responses_still_arrive() {
+    let (_door, shout) = channel(shout).unwrap();
+    let arg = OwnedDoorArgument::new(vec![b'a'; 4096], 16);
+
+    let response = shout.call_owned_arg(arg).unwrap();
+    assert_eq!(response.data(), vec![b'A'; 4096]);
+}
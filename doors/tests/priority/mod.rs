@@ -0,0 +1,29 @@
+use doors::server::{Request, Response};
+use doors::testing::channel;
+
+/// Answer urgent requests in capitals, and everything else as it came.
+#[doors::server_procedure]
+fn triage(x: Request<'_>) -> Response<Vec<u8>> {
+    match x.priority() {
+        Some((priority, data)) if priority > 100 => {
+            Response::new(data.to_ascii_uppercase())
+        }
+        Some((_, data)) => Response::new(data.to_vec()),
+        None => Response::new(b"untagged".to_vec()),
+    }
+}
+
+#[test]
+fn servers_read_priorities() {
+    let (_door, client) = channel(triage).unwrap();
+    let response = client.call_with_priority(b"fire", 200).unwrap();
+    assert_eq!(response, "FIRE");
+    let response = client.call_with_priority(b"later", 1).unwrap();
+    assert_eq!(response, "later");
+}
+
+#[test]
+fn untagged_requests_have_no_priority() {
+    let (_door, client) = channel(triage).unwrap();
+    assert_eq!(client.call_with_data(b"hi").unwrap(), "untagged");
+}
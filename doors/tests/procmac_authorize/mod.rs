@@ -0,0 +1,33 @@
+use doors::illumos::Ucred;
+use doors::server::{Request, Response};
+use doors::testing::channel;
+
+/// Only answer calls from this very process.
+#[doors::server_procedure(authorize)]
+fn same_process(x: Request<'_>, who: Ucred) -> Response<Vec<u8>> {
+    match who.pid() {
+        Some(pid) if pid as u32 == std::process::id() => {
+            Response::new(x.data.to_vec())
+        }
+        _ => Response::err_bytes(1, b"who are you?"),
+    }
+}
+
+/// Answer with the caller's euid, from a scrubbed frame.
+#[doors::server_procedure(authorize, zeroize)]
+fn euid(_x: Request<'_>, who: Ucred) -> Response<[u8; 4]> {
+    Response::new(who.euid().unwrap_or(u32::MAX).to_le_bytes())
+}
+
+#[test]
+fn authorizing_handlers_see_their_callers() {
+    let (_door, client) = channel(same_process).unwrap();
+    assert_eq!(client.call_with_data(b"hello").unwrap(), "hello");
+}
+
+#[test]
+fn authorizing_handlers_can_be_zeroized() {
+    let (_door, client) = channel(euid).unwrap();
+    let response = client.call_with_data(&[]).unwrap();
+    assert_eq!(response, unsafe { libc::geteuid() }.to_le_bytes());
+}
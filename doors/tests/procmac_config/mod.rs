@@ -0,0 +1,8 @@
+use doors::Client;
+
+#[test]
+fn config_is_served_from_state() {
+    let config = Client::open("/tmp/procmac_config.door").unwrap();
+    let response = config.call_with_data(&[]).unwrap();
+    assert_eq!(response, "listen=0.0.0.0:80\nworkers=4\n");
+}
@@ -0,0 +1,64 @@
+use doors::server::{Door, DoorServer, Request, Response, Serve};
+use doors::testing::TempDoor;
+use doors::Client;
+use std::borrow::Cow;
+
+/// Capitalize the request, borrowing it if it is already capitalized.
+fn capitalize(data: &[u8]) -> Cow<'_, [u8]> {
+    match data.iter().any(u8::is_ascii_lowercase) {
+        true => Cow::Owned(data.to_ascii_uppercase()),
+        false => Cow::Borrowed(data),
+    }
+}
+
+#[doors::server_procedure]
+fn cow_capitalize(x: Request<'_>) -> Response<Cow<'_, [u8]>> {
+    Response::new(capitalize(x.data))
+}
+
+#[test]
+fn borrowed_cows_are_returned_from_the_request() {
+    let door = TempDoor::new(Door::create(cow_capitalize).unwrap()).unwrap();
+    let client = door.client().unwrap();
+
+    let response = client.call_with_data(b"HELLO").unwrap();
+    assert_eq!(response, b"HELLO");
+}
+
+#[test]
+fn owned_cows_are_returned_and_dropped() {
+    let door = TempDoor::new(Door::create(cow_capitalize).unwrap()).unwrap();
+    let client = door.client().unwrap();
+
+    // Enough calls to cycle through the door threads, so that each of them
+    // drops a stashed response or two along the way.
+    for _ in 0..1024 {
+        let response = client.call_with_data(b"hello").unwrap();
+        assert_eq!(response, b"HELLO");
+    }
+}
+
+struct Capitalizer;
+
+impl Serve for Capitalizer {
+    type Data<'a> = Cow<'a, [u8]>;
+
+    fn serve<'a>(
+        &'a self,
+        request: Request<'a, Self>,
+    ) -> Response<Cow<'a, [u8]>> {
+        Response::new(capitalize(request.data))
+    }
+}
+
+#[test]
+fn door_servers_can_return_cows() {
+    let server =
+        DoorServer::new(Capitalizer, "/tmp/doorserver_cow.door").unwrap();
+    let client = Client::open(server.path()).unwrap();
+
+    let response = client.call_with_data(b"hello").unwrap();
+    assert_eq!(response, b"HELLO");
+    let response = client.call_with_data(b"HELLO").unwrap();
+    assert_eq!(response, b"HELLO");
+}
@@ -0,0 +1,73 @@
+use doors::illumos::DoorFd;
+use doors::server::{Door, Request, Response};
+use doors::testing::TempDoor;
+use doors::Client;
+use std::os::fd::AsRawFd;
+use std::os::fd::FromRawFd;
+use std::os::fd::IntoRawFd;
+
+#[doors::server_procedure]
+fn echo(x: Request<'_>) -> Response<Vec<u8>> {
+    Response::new(x.data.to_vec())
+}
+
+#[doors::server_procedure(state = Door)]
+fn find_echo(x: Request<'_, Door>) -> Response<[u8; 0]> {
+    let release = x.data.first() == Some(&1);
+    Response::empty().add_door_client(x.state, release)
+}
+
+fn lookup(directory: &Client, release: bool) -> Client {
+    let response = directory.call_with_data(&[release as u8]).unwrap();
+    let mut fds = response.into_descriptors();
+    let fd = fds.next().unwrap();
+    assert!(fds.next().is_none());
+    unsafe { Client::from_raw_fd(fd.into_raw_fd()) }
+}
+
+#[test]
+fn directories_hand_out_working_door_clients() {
+    let echo: &'static Door = Box::leak(Box::new(Door::create(echo).unwrap()));
+    let door = Door::create_with_state(find_echo, echo).unwrap();
+    let door = TempDoor::new(door).unwrap();
+    let directory = door.client().unwrap();
+
+    for release in [false, true] {
+        let client = lookup(&directory, release);
+        let response = client.call_with_data(b"hello").unwrap();
+        assert_eq!(response.data(), b"hello");
+    }
+
+    // Handing out clients, released or not, leaves the door itself alone.
+    let arg = doors::DoorArgument::new(b"again", &[], &mut []);
+    let response = echo.call_self(arg).unwrap();
+    assert_eq!(response.data(), b"again");
+}
+
+#[test]
+fn received_door_descriptors_become_clients() {
+    let echo: &'static Door = Box::leak(Box::new(Door::create(echo).unwrap()));
+    let door = Door::create_with_state(find_echo, echo).unwrap();
+    let door = TempDoor::new(door).unwrap();
+    let directory = door.client().unwrap();
+
+    let response = directory.call_with_data(&[0]).unwrap();
+    let fd = response.single_descriptor().unwrap().as_raw_fd();
+    let client = DoorFd::new(fd, false).into_client().unwrap();
+    let response = client.call_with_data(b"hello").unwrap();
+    assert_eq!(response.data(), b"hello");
+}
+
+#[test]
+fn other_descriptors_do_not_become_clients() {
+    let file = std::fs::File::open("/dev/null").unwrap();
+    let raw = file.into_raw_fd();
+    let (fd, e) = DoorFd::new(raw, false).into_client().err().unwrap();
+    assert_eq!(e.kind(), std::io::ErrorKind::InvalidInput);
+    assert_eq!(fd.as_raw_fd(), raw);
+
+    // The descriptor came back to us, so dropping it is what closes it.
+    assert_ne!(unsafe { libc::fcntl(raw, libc::F_GETFD) }, -1);
+    drop(fd);
+    assert_eq!(unsafe { libc::fcntl(raw, libc::F_GETFD) }, -1);
+}
@@ -1,13 +1,67 @@
-use doors::Client;
+use doors::illumos::door_h::door_arg_t;
+use doors::server::{Door, Request, Response};
+use doors::testing::TempDoor;
 use doors::DoorArgument;
 
+#[doors::server_procedure]
+fn double(payload: Request<'_>) -> Response<[u8; 1]> {
+    match payload.data_array() {
+        Some([n]) => Response::new([n * 2]),
+        None => Response::empty(),
+    }
+}
+
 #[test]
 fn procedural_macro_double_u8() {
-    let double = Client::open("/tmp/procmac_double.door").unwrap();
+    let door = TempDoor::new(Door::create(double).unwrap()).unwrap();
+    let double = door.client().unwrap();
 
-    let mut rbuf: [u8; 1] = [0];
+    let mut rbuf = [0; DOUBLE_RESPONSE_SIZE];
 
     let arg = DoorArgument::new(&[111], &[], &mut rbuf);
     double.call(arg).unwrap();
     assert_eq!(rbuf[0], 222);
 }
+
+#[test]
+fn requests_of_the_wrong_length_get_no_answer() {
+    let door = TempDoor::new(Door::create(double).unwrap()).unwrap();
+    let double = door.client().unwrap();
+
+    assert!(double.call_with_data(&[]).unwrap().data().is_empty());
+    assert!(double.call_with_data(&[1, 2]).unwrap().data().is_empty());
+    assert_eq!(double.call_with_data(&[3]).unwrap(), [6]);
+}
+
+#[test]
+fn temp_doors_clean_up_after_themselves() {
+    let door = TempDoor::new(Door::create(double).unwrap()).unwrap();
+    let other = TempDoor::new(Door::create(double).unwrap()).unwrap();
+    assert_ne!(door.path(), other.path());
+
+    let path = door.path().to_path_buf();
+    assert!(path.exists());
+    drop(door);
+    assert!(!path.exists());
+}
+
+#[test]
+fn raw_calls_use_the_door_arg_t_as_given() {
+    let door = TempDoor::new(Door::create(double).unwrap()).unwrap();
+    let double = door.client().unwrap();
+
+    let data = [21u8];
+    let mut rbuf = [0u8; 1];
+    let mut arg = door_arg_t {
+        data_ptr: data.as_ptr() as *const libc::c_char,
+        data_size: data.len(),
+        desc_ptr: std::ptr::null(),
+        desc_num: 0,
+        rbuf: rbuf.as_mut_ptr() as *const libc::c_char,
+        rsize: rbuf.len(),
+    };
+    unsafe { double.raw_call(&mut arg) }.unwrap();
+    assert_eq!(arg.rbuf as *const u8, rbuf.as_ptr());
+    assert_eq!(arg.data_size, 1);
+    assert_eq!(rbuf[0], 42);
+}
@@ -0,0 +1,59 @@
+use doors::server::{Request, Response};
+use doors::Client;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+#[doors::server_procedure(install = "/tmp/procmac_install_double.door")]
+fn double(x: Request<'_>) -> Response<[u8; 1]> {
+    Response::new([x.data[0] * 2])
+}
+
+struct Counter(AtomicU8);
+
+mod aliased {
+    use doors::server::{Request, Response};
+
+    /// Installers must not trip over a `Result` of the module's own.
+    #[allow(dead_code)]
+    type Result<T> = std::result::Result<T, ()>;
+
+    #[doors::server_procedure(install = "/tmp/procmac_install_triple.door")]
+    pub fn triple(x: Request<'_>) -> Response<[u8; 1]> {
+        Response::new([x.data[0] * 3])
+    }
+}
+
+#[doors::server_procedure(
+    state = Counter,
+    install = "/tmp/procmac_install_count.door"
+)]
+fn count(x: Request<'_, Counter>) -> Response<[u8; 1]> {
+    Response::new([x.state.0.fetch_add(1, Ordering::SeqCst) + 1])
+}
+
+#[test]
+fn installers_create_and_install_the_door() {
+    let _door = install_double().unwrap();
+    let client = Client::open("/tmp/procmac_install_double.door").unwrap();
+    assert_eq!(client.call_with_data(&[21]).unwrap(), [42]);
+
+    // Installing again replaces whatever was at the path.
+    let _again = install_double().unwrap();
+    let client = Client::open("/tmp/procmac_install_double.door").unwrap();
+    assert_eq!(client.call_with_data(&[4]).unwrap(), [8]);
+}
+
+#[test]
+fn stateful_installers_take_the_state() {
+    static COUNTER: Counter = Counter(AtomicU8::new(0));
+    let _door = install_count(&COUNTER).unwrap();
+    let client = Client::open("/tmp/procmac_install_count.door").unwrap();
+    assert_eq!(client.call_with_data(&[]).unwrap(), [1]);
+    assert_eq!(client.call_with_data(&[]).unwrap(), [2]);
+}
+
+#[test]
+fn installers_ignore_result_aliases() {
+    let _door = aliased::install_triple().unwrap();
+    let client = Client::open("/tmp/procmac_install_triple.door").unwrap();
+    assert_eq!(client.call_with_data(&[7]).unwrap(), [21]);
+}
@@ -0,0 +1,14 @@
+use doors::Client;
+use doors::DoorArgument;
+
+#[test]
+fn nested_door_calls_do_not_deadlock() {
+    let outer = Client::open("/tmp/procmac_nested_outer.door").unwrap();
+
+    for _ in 0..16 {
+        let mut rbuf = [0];
+        let arg = DoorArgument::new(&[1], &[], &mut rbuf);
+        outer.call(arg).unwrap();
+        assert_eq!(rbuf[0], 4);
+    }
+}
@@ -1,4 +1,6 @@
 use doors::illumos::door_h;
+use doors::Client;
+use doors::DoorArgument;
 use std::ffi::CString;
 use std::io::Read;
 use std::io::Write;
@@ -65,3 +67,61 @@ fn can_receive_file_descriptor() {
     // favor.
     unsafe { libc::free(rbuf as *mut libc::c_void) };
 }
+
+#[test]
+fn can_receive_several_file_descriptors() {
+    let txt_path = Path::new("/tmp/procmac_open_twice.txt");
+    let mut txt = std::fs::File::create(txt_path).expect("create txt");
+    writeln!(txt, "Hello, World!").expect("write txt");
+    drop(txt);
+    let txt_path_cstring = CString::new(txt_path.to_str().unwrap()).unwrap();
+
+    let client = Client::open("/tmp/procmac_open_twice.door").unwrap();
+    let arg =
+        DoorArgument::new(txt_path_cstring.as_bytes_with_nul(), &[], &mut []);
+    let response = client.call(arg).unwrap();
+
+    let params = response.as_door_arg_t();
+    let door_desc_ts = unsafe {
+        std::slice::from_raw_parts::<door_h::door_desc_t>(
+            params.desc_ptr,
+            params.desc_num.try_into().unwrap(),
+        )
+    };
+    assert_eq!(door_desc_ts.len(), 2);
+
+    for desc in door_desc_ts {
+        let d_desc = unsafe { desc.d_data.d_desc };
+        let raw_fd = d_desc.d_descriptor as RawFd;
+        let mut txt = unsafe { std::fs::File::from_raw_fd(raw_fd) };
+        let mut buffer = String::new();
+        txt.read_to_string(&mut buffer).expect("read txt");
+        assert_eq!(&buffer, "Hello, World!\n");
+    }
+}
+
+#[test]
+fn can_take_ownership_of_returned_descriptors() {
+    let txt_path = Path::new("/tmp/procmac_open_owned.txt");
+    let mut txt = std::fs::File::create(txt_path).expect("create txt");
+    writeln!(txt, "Hello, World!").expect("write txt");
+    drop(txt);
+    let txt_path_cstring = CString::new(txt_path.to_str().unwrap()).unwrap();
+
+    let client = Client::open("/tmp/procmac_open_twice.door").unwrap();
+    let data = txt_path_cstring.as_bytes_with_nul();
+    let arg = DoorArgument::new(data, &[], &mut []);
+    let response = client.call(arg).unwrap();
+
+    let files: Vec<std::fs::File> = response
+        .into_descriptors()
+        .map(std::fs::File::from)
+        .collect();
+    assert_eq!(files.len(), 2);
+
+    for mut txt in files {
+        let mut buffer = String::new();
+        txt.read_to_string(&mut buffer).expect("read txt");
+        assert_eq!(&buffer, "Hello, World!\n");
+    }
+}
@@ -0,0 +1,69 @@
+use doors::illumos::door_h;
+use doors::illumos::DoorFd;
+use doors::Client;
+use doors::DoorArgument;
+use std::io::Read;
+use std::io::Write;
+use std::os::fd::AsRawFd;
+use std::os::fd::FromRawFd;
+use std::os::fd::RawFd;
+
+#[test]
+fn stashed_descriptor_outlives_the_call() {
+    let txt_path = "/tmp/procmac_stash.txt";
+    let mut txt = std::fs::File::create(txt_path).expect("create txt");
+    writeln!(txt, "Hello, World!").expect("write txt");
+    drop(txt);
+
+    // Hand the server a copy of our descriptor, then close our own.
+    let stash = Client::open("/tmp/procmac_stash_store.door").unwrap();
+    let txt = std::fs::File::open(txt_path).expect("open txt");
+    let fds = [DoorFd::new(txt.as_raw_fd(), false)];
+    let arg = DoorArgument::new(&[], &fds, &mut []);
+    stash.call(arg).unwrap();
+    drop(txt);
+
+    // Ask for it back on a separate call.
+    let fetch = Client::open("/tmp/procmac_stash_fetch.door").unwrap();
+    let arg = DoorArgument::new(&[], &[], &mut []);
+    let response = fetch.call(arg).unwrap();
+
+    let params = response.as_door_arg_t();
+    let door_desc_ts = unsafe {
+        std::slice::from_raw_parts::<door_h::door_desc_t>(
+            params.desc_ptr,
+            params.desc_num.try_into().unwrap(),
+        )
+    };
+    assert_eq!(door_desc_ts.len(), 1);
+
+    let d_desc = unsafe { door_desc_ts[0].d_data.d_desc };
+    let raw_fd = d_desc.d_descriptor as RawFd;
+    let mut txt = unsafe { std::fs::File::from_raw_fd(raw_fd) };
+    let mut buffer = String::new();
+    txt.read_to_string(&mut buffer).expect("read txt");
+    assert_eq!(&buffer, "Hello, World!\n");
+}
+
+#[test]
+fn descriptors_can_be_sent_without_data() {
+    let txt_path = "/tmp/procmac_stash_fds_only.txt";
+    let mut txt = std::fs::File::create(txt_path).expect("create txt");
+    writeln!(txt, "Hello, World!").expect("write txt");
+    drop(txt);
+
+    let stash = Client::open("/tmp/procmac_stash_store.door").unwrap();
+    let txt = std::fs::File::open(txt_path).expect("open txt");
+    stash
+        .call_with_fds(&[DoorFd::new(txt.as_raw_fd(), false)])
+        .unwrap();
+    drop(txt);
+
+    let fetch = Client::open("/tmp/procmac_stash_fetch.door").unwrap();
+    let response = fetch.call_with_data(&[]).unwrap();
+    let mut txt: std::fs::File =
+        response.into_descriptors().next().unwrap().into();
+    let mut buffer = String::new();
+    txt.read_to_string(&mut buffer).expect("read txt");
+    assert_eq!(&buffer, "Hello, World!\n");
+}
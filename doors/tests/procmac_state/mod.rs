@@ -0,0 +1,16 @@
+use doors::Client;
+use doors::DoorArgument;
+
+#[test]
+fn state_is_shared_between_calls() {
+    let increment = Client::open("/tmp/procmac_state.door").unwrap();
+
+    let mut rbuf: [u8; 1] = [0];
+    let arg = DoorArgument::new(&[], &[], &mut rbuf);
+    increment.call(arg).unwrap();
+    let first = rbuf[0];
+
+    let arg = DoorArgument::new(&[], &[], &mut rbuf);
+    increment.call(arg).unwrap();
+    assert_eq!(rbuf[0], first + 1);
+}
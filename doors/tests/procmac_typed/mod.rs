@@ -0,0 +1,141 @@
+use doors::testing::channel;
+use doors::typed::{AsBytes, FromBytes, TypedCallError};
+use doors::{Client, DoorCallError, DoorError, DoorPayload};
+use std::sync::OnceLock;
+
+static ITSELF: OnceLock<Client> = OnceLock::new();
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[repr(C)]
+struct Transfer {
+    amount: u64,
+    from: u32,
+    to: u32,
+}
+
+// Safety: repr(C), no padding, integers only.
+unsafe impl AsBytes for Transfer {}
+unsafe impl FromBytes for Transfer {}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[repr(C)]
+struct Receipt {
+    id: u64,
+    balances: [i64; 2],
+}
+
+// Safety: repr(C), no padding, integers only.
+unsafe impl AsBytes for Receipt {}
+unsafe impl FromBytes for Receipt {}
+
+#[doors::server_procedure_typed]
+fn transfer(t: Transfer) -> Receipt {
+    assert_ne!(t.from, t.to, "cannot transfer to the same account");
+    Receipt {
+        id: (t.from as u64) << 32 | t.to as u64,
+        balances: [-(t.amount as i64), t.amount as i64],
+    }
+}
+
+#[test]
+fn structs_round_trip() {
+    let (_door, client) = channel(transfer).unwrap();
+    let request = Transfer {
+        amount: 250,
+        from: 1,
+        to: 2,
+    };
+
+    let receipt: Receipt = client.call_typed(&request).unwrap();
+    assert_eq!(
+        receipt,
+        Receipt {
+            id: 1 << 32 | 2,
+            balances: [-250, 250],
+        }
+    );
+}
+
+#[test]
+fn wrong_sizes_are_refused() {
+    let (_door, client) = channel(transfer).unwrap();
+
+    let result: Result<Receipt, _> = client.call_typed(&7u32);
+    match result {
+        Err(TypedCallError::Server(e)) => {
+            assert_eq!(e.code, DoorError::WRONG_SIZE)
+        }
+        other => panic!("expected a wrong size error, got {:?}", other),
+    }
+
+    let request = Transfer {
+        amount: 1,
+        from: 1,
+        to: 2,
+    };
+    let result: Result<u32, _> = client.call_typed(&request);
+    assert_eq!(result, Err(TypedCallError::WrongSize(24)));
+}
+
+#[test]
+fn panics_are_reported() {
+    let (_door, client) = channel(transfer).unwrap();
+    let request = Transfer {
+        amount: 1,
+        from: 3,
+        to: 3,
+    };
+
+    let result: Result<Receipt, _> = client.call_typed(&request);
+    match result {
+        Err(TypedCallError::Server(e)) => assert!(e.is_server_panic()),
+        other => panic!("expected a panic, got {:?}", other),
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, DoorPayload)]
+#[repr(C)]
+struct Span {
+    start: Point,
+    end: Point,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, DoorPayload)]
+#[repr(C)]
+struct Point(i32, i32);
+
+#[doors::server_procedure_typed]
+fn reverse(s: Span) -> Span {
+    Span {
+        start: s.end,
+        end: s.start,
+    }
+}
+
+#[test]
+fn derived_payloads_round_trip() {
+    let (_door, client) = channel(reverse).unwrap();
+    let span = Span {
+        start: Point(1, 2),
+        end: Point(3, 4),
+    };
+    let reversed: Span = client.call_typed(&span).unwrap();
+    assert_eq!(reversed.start, Point(3, 4));
+    assert_eq!(reversed.end, Point(1, 2));
+}
+
+/// Answers 1 if calling its own door is refused, as it should be.
+#[doors::server_procedure_typed]
+fn call_itself(n: u32) -> u32 {
+    match ITSELF.get().unwrap().call_typed::<u32, u32>(&n) {
+        Err(TypedCallError::Call(DoorCallError::WouldDeadlock)) => 1,
+        _ => 0,
+    }
+}
+
+#[test]
+fn typed_handlers_are_invocations() {
+    let (door, client) = channel(call_itself).unwrap();
+    assert!(ITSELF.set(door.loopback_client().unwrap()).is_ok());
+    assert_eq!(client.call_typed::<u32, u32>(&7).unwrap(), 1);
+}
@@ -0,0 +1,88 @@
+use doors::illumos::DoorAttributes;
+use doors::server::{Door, Request, Response};
+use doors::DoorArgument;
+use std::os::fd::AsRawFd;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+static UNREF: AtomicUsize = AtomicUsize::new(0);
+static UNREF_MULTI: AtomicUsize = AtomicUsize::new(0);
+static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+#[doors::server_procedure(unref)]
+fn count_unref(x: Request<'_>) -> Response<[u8; 0]> {
+    if x.is_unref() {
+        UNREF.fetch_add(1, Ordering::SeqCst);
+    }
+    Response::empty()
+}
+
+#[doors::server_procedure(unref)]
+fn count_unref_multi(x: Request<'_>) -> Response<[u8; 0]> {
+    if x.is_unref() {
+        UNREF_MULTI.fetch_add(1, Ordering::SeqCst);
+    }
+    Response::empty()
+}
+
+/// Would panic on an unreferenced notification, having no data to index.
+#[doors::server_procedure]
+fn first_byte(x: Request<'_>) -> Response<[u8; 1]> {
+    CALLS.fetch_add(1, Ordering::SeqCst);
+    Response::new([x.data[0]])
+}
+
+/// Open a client to `door` and close it again, so that the door briefly gains
+/// and then loses a reference.
+fn visit(door: &Door) {
+    let fd = unsafe { libc::dup(door.as_raw_fd()) };
+    assert_ne!(fd, -1);
+    unsafe { libc::close(fd) };
+}
+
+/// Wait a while for `counter` to reach at least `n`.
+fn wait_for(counter: &AtomicUsize, n: usize) {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while counter.load(Ordering::SeqCst) < n {
+        assert!(Instant::now() < deadline, "no unref notification");
+        std::thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn unref_multi_notifies_every_time() {
+    let attrs = DoorAttributes::unref_multi();
+    let door = Door::create_with_attributes(count_unref_multi, attrs).unwrap();
+    for n in 1..=3 {
+        visit(&door);
+        wait_for(&UNREF_MULTI, n);
+    }
+}
+
+#[test]
+fn unref_notifies_once() {
+    let attrs = DoorAttributes::unref();
+    let door = Door::create_with_attributes(count_unref, attrs).unwrap();
+    visit(&door);
+    wait_for(&UNREF, 1);
+
+    visit(&door);
+    std::thread::sleep(Duration::from_millis(100));
+    assert_eq!(UNREF.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn handlers_only_see_notifications_if_they_ask() {
+    let attrs = DoorAttributes::unref_multi();
+    let door = Door::create_with_attributes(first_byte, attrs).unwrap();
+    for _ in 0..3 {
+        visit(&door);
+    }
+    std::thread::sleep(Duration::from_millis(100));
+    assert_eq!(CALLS.load(Ordering::SeqCst), 0);
+
+    let arg = DoorArgument::new(&[7], &[], &mut []);
+    let response = door.call_self(arg).unwrap();
+    assert_eq!(response.data(), &[7]);
+    assert_eq!(CALLS.load(Ordering::SeqCst), 1);
+}
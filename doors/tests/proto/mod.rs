@@ -0,0 +1,69 @@
+use doors::proto::MessageCallError;
+use doors::server::{Request, Response};
+use doors::testing::channel;
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct Sum {
+    #[prost(int64, repeated, tag = "1")]
+    terms: Vec<i64>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct Total {
+    #[prost(int64, tag = "1")]
+    total: i64,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct Label {
+    #[prost(string, tag = "1")]
+    text: String,
+}
+
+#[doors::server_procedure]
+fn add(x: Request<'_>) -> Response<Vec<u8>> {
+    match x.message::<Sum>() {
+        Ok(sum) => Response::message(&Total {
+            total: sum.terms.iter().sum(),
+        }),
+        Err(e) => Response::err_bytes(1, e.to_string().as_bytes()),
+    }
+}
+
+#[test]
+fn messages_round_trip() {
+    let (_door, add) = channel(add).unwrap();
+    let sum = Sum {
+        terms: vec![1, 2, 3, -10],
+    };
+    let total: Total = add.call_message(&sum).unwrap();
+    assert_eq!(total.total, -4);
+}
+
+#[test]
+fn large_messages_round_trip() {
+    let (_door, add) = channel(add).unwrap();
+    let sum = Sum {
+        terms: (1..=10_000).collect(),
+    };
+    let total: Total = add.call_message(&sum).unwrap();
+    assert_eq!(total.total, 50_005_000);
+}
+
+#[test]
+fn undecodable_requests_are_reported() {
+    let (_door, add) = channel(add).unwrap();
+    let response = add.call_with_data(&[0xff; 4]).unwrap();
+    assert_eq!(response.as_result().unwrap_err().code, 1);
+}
+
+#[test]
+fn undecodable_responses_are_reported() {
+    let (_door, add) = channel(add).unwrap();
+    let sum = Sum { terms: vec![1] };
+    let wrong: Result<Label, _> = add.call_message(&sum);
+    match wrong {
+        Err(MessageCallError::Decode(_)) => (),
+        other => panic!("expected a decode error, got {:?}", other),
+    }
+}
@@ -0,0 +1,39 @@
+use doors::illumos::DoorAttributes;
+use doors::illumos::DoorFd;
+use doors::server::{Door, Request, Response};
+use doors::testing::TempDoor;
+use doors::DoorArgument;
+use doors::DoorCallError;
+use std::os::fd::AsRawFd;
+
+#[doors::server_procedure]
+fn ignore(_x: Request<'_>) -> Response<[u8; 0]> {
+    Response::empty()
+}
+
+#[test]
+fn refused_descriptors_are_caught_before_the_call() {
+    let attrs = DoorAttributes::refuse_desc();
+    let door = Door::create_with_attributes(ignore, attrs).unwrap();
+    let door = TempDoor::new(door).unwrap();
+    let client = door.client().unwrap();
+
+    let file = std::fs::File::open("/dev/null").unwrap();
+    let fds = [DoorFd::new(file.as_raw_fd(), false)];
+    let arg = DoorArgument::new(&[], &fds, &mut []);
+    match client.call_checked(arg) {
+        Err(e) => assert_eq!(e, DoorCallError::DescriptorsRefused),
+        Ok(_) => panic!("descriptors should have been refused"),
+    }
+}
+
+#[test]
+fn checked_calls_without_descriptors_go_through() {
+    let attrs = DoorAttributes::refuse_desc();
+    let door = Door::create_with_attributes(ignore, attrs).unwrap();
+    let door = TempDoor::new(door).unwrap();
+    let client = door.client().unwrap();
+
+    let arg = DoorArgument::new(&[], &[], &mut []);
+    assert!(client.call_checked(arg).is_ok());
+}
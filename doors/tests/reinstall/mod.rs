@@ -0,0 +1,34 @@
+use doors::server::{Door, Request, Response};
+use doors::Client;
+
+#[doors::server_procedure]
+fn blue(_x: Request<'_>) -> Response<[u8; 4]> {
+    Response::new(*b"blue")
+}
+
+#[test]
+fn doors_move_to_new_paths() {
+    let old_path = std::env::temp_dir().join("reinstall_old.door");
+    let new_path = std::env::temp_dir().join("reinstall_new.door");
+    std::fs::remove_file(&new_path).ok();
+    let door = Door::create(blue).unwrap();
+    door.force_install(&old_path).unwrap();
+    let before = Client::open(&old_path).unwrap();
+
+    door.reinstall(&old_path, &new_path).unwrap();
+    assert!(!old_path.exists());
+
+    // Clients from before the move still reach the door, and new clients
+    // find it at its new path.
+    let after = Client::open(&new_path).unwrap();
+    assert_eq!(before.call_with_data(&[]).unwrap(), "blue");
+    assert_eq!(after.call_with_data(&[]).unwrap(), "blue");
+
+    door.reinstall(&new_path, &old_path).unwrap();
+    assert!(!new_path.exists());
+    let back = Client::open(&old_path).unwrap();
+    assert_eq!(back.call_with_data(&[]).unwrap(), "blue");
+
+    doors::illumos::fdetach(&old_path).ok();
+    std::fs::remove_file(&old_path).ok();
+}
@@ -0,0 +1,98 @@
+use doors::illumos::door_h::door_desc_t;
+use doors::illumos::DoorFd;
+use doors::server::{Door, Request, Response};
+use doors::testing::channel;
+use std::ffi::CStr;
+use std::fs::File;
+use std::os::fd::IntoRawFd;
+
+#[doors::server_procedure]
+fn imperative(x: Request<'_>) -> Response<[u8; 0]> {
+    if x.data.is_empty() {
+        x.reply(b"nothing to do");
+    }
+    if x.data == b"file" {
+        let null = File::open("/dev/null").unwrap();
+        x.reply_with(b"here", &[DoorFd::new(null.into_raw_fd(), true)]);
+    }
+    x.reply(x.data)
+}
+
+/// Open the file named in the request, refusing anything that isn't one.
+#[doors::server_procedure]
+fn open_file(x: Request<'_>) -> Response<[u8; 0]> {
+    let path = match CStr::from_bytes_with_nul(x.data) {
+        Ok(path) => path,
+        Err(_) => x.reply_error(libc::EINVAL as u32),
+    };
+    let file = match File::open(path.to_string_lossy().as_ref()) {
+        Ok(file) => file,
+        Err(_) => x.reply_error(libc::ENOENT as u32),
+    };
+    x.reply_with(&[], &[DoorFd::new(file.into_raw_fd(), true)])
+}
+
+#[test]
+fn handlers_can_reply_imperatively() {
+    let (_door, client) = channel(imperative).unwrap();
+    assert_eq!(client.call_with_data(b"").unwrap(), "nothing to do");
+    assert_eq!(client.call_with_data(b"echo").unwrap(), "echo");
+}
+
+#[test]
+fn replies_can_carry_descriptors() {
+    let (_door, client) = channel(imperative).unwrap();
+    let response = client.call_with_data(b"file").unwrap();
+    assert_eq!(response, "here");
+    assert_eq!(response.into_descriptors().count(), 1);
+}
+
+#[test]
+fn handlers_can_reject_requests_imperatively() {
+    let (_door, client) = channel(open_file).unwrap();
+
+    let response = client.call_with_data(b"no nul").unwrap();
+    let error = response.as_result().unwrap_err();
+    assert_eq!(error.code, libc::EINVAL as u32);
+    assert!(error.detail.is_empty());
+
+    let response = client.call_with_data(b"/no/such/file\0").unwrap();
+    assert_eq!(response.as_result().unwrap_err().code, libc::ENOENT as u32);
+
+    let response = client.call_with_data(b"/dev/null\0").unwrap();
+    assert!(response.as_result().is_ok());
+    assert_eq!(response.into_descriptors().count(), 1);
+}
+
+/// Answers "macro" through a generated procedure, which enters an invocation,
+/// and anything else by hand, without one.
+extern "C" fn mixed(
+    cookie: *const libc::c_void,
+    argp: *const libc::c_char,
+    arg_size: libc::size_t,
+    dp: *const door_desc_t,
+    n_desc: libc::c_uint,
+) {
+    let data =
+        unsafe { std::slice::from_raw_parts(argp as *const u8, arg_size) };
+    if data == b"macro" {
+        return imperative(cookie, argp, arg_size, dp, n_desc);
+    }
+    let request = Request {
+        cookie: cookie as u64,
+        data,
+        descriptors: &[],
+        state: &(),
+    };
+    request.reply(b"by hand")
+}
+
+#[test]
+fn hand_written_replies_follow_generated_ones() {
+    // One thread answers every call, so each kind follows the other on it.
+    let door = Door::create_private(mixed, 1).unwrap();
+    let client = door.loopback_client().unwrap();
+    assert_eq!(client.call_with_data(b"macro").unwrap(), "macro");
+    assert_eq!(client.call_with_data(b"hand").unwrap(), "by hand");
+    assert_eq!(client.call_with_data(b"macro").unwrap(), "macro");
+}
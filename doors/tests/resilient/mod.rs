@@ -0,0 +1,54 @@
+use doors::server::{Door, Request, Response};
+use doors::testing::TempDoor;
+use doors::{DoorCallError, ResilientClient};
+use std::time::Duration;
+
+#[doors::server_procedure]
+fn double(x: Request<'_>) -> Response<[u8; 1]> {
+    Response::new([x.data[0] * 2])
+}
+
+/// Stop serving at `door`'s path, and start again with a fresh door.
+fn restart(door: TempDoor) -> Door {
+    let path = door.path().to_path_buf();
+    drop(door);
+    let door = Door::create(double).unwrap();
+    door.install(&path).unwrap();
+    door
+}
+
+/// Undo what `restart` did.
+fn clean_up(path: &std::path::Path) {
+    doors::illumos::fdetach(path).unwrap();
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn idempotent_calls_survive_a_restart() {
+    let door = TempDoor::new(Door::create(double).unwrap()).unwrap();
+    let path = door.path().to_path_buf();
+    let mut client = ResilientClient::open(&path)
+        .unwrap()
+        .with_retries(2, Duration::from_millis(1));
+    assert_eq!(client.server_pid(), Some(std::process::id()));
+    assert_eq!(client.call_idempotent(&[4]).unwrap(), [8]);
+
+    let _door = restart(door);
+    assert_eq!(client.call_idempotent(&[5]).unwrap(), [10]);
+    clean_up(&path);
+}
+
+#[test]
+fn other_calls_are_not_repeated() {
+    let door = TempDoor::new(Door::create(double).unwrap()).unwrap();
+    let path = door.path().to_path_buf();
+    let mut client = ResilientClient::open(&path).unwrap();
+
+    let _door = restart(door);
+    let result = client.call_with_data(&[5]);
+    assert_eq!(result.unwrap_err(), DoorCallError::Revoked);
+
+    // The next call goes to the new door.
+    assert_eq!(client.call_with_data(&[6]).unwrap(), [12]);
+    clean_up(&path);
+}
@@ -0,0 +1,44 @@
+use doors::server::{Request, Response};
+use doors::testing::channel;
+
+#[doors::server_procedure]
+fn echo(x: Request<'_>) -> Response<&[u8]> {
+    Response::new(x.data)
+}
+
+#[test]
+fn registered_buffers_avoid_mapping_responses() {
+    let large = vec![7; 64 * 1024];
+
+    let (_door, client) = channel(echo).unwrap();
+    let client = client.with_default_rbuf(16);
+    let mapped = (0..4)
+        .map(|_| client.call_with_data(&large).unwrap())
+        .filter(|response| response.rbuf_is_mapped())
+        .count();
+    assert_eq!(mapped, 4);
+
+    let (_door, mut client) = channel(echo).unwrap();
+    client.register_response_buffer(128 * 1024);
+    let responses: Vec<_> = (0..4)
+        .map(|_| client.call_with_data(&large).unwrap())
+        .collect();
+    for response in &responses {
+        assert!(!response.rbuf_is_mapped());
+        assert_eq!(*response, &large[..]);
+    }
+}
+
+#[test]
+fn responses_larger_than_the_buffer_are_still_mapped() {
+    let (_door, mut client) = channel(echo).unwrap();
+    client.register_response_buffer(16);
+    let small = client.call_with_data(b"small").unwrap();
+    assert!(!small.rbuf_is_mapped());
+    assert_eq!(small, "small");
+
+    let large = vec![7; 64 * 1024];
+    let response = client.call_with_data(&large).unwrap();
+    assert!(response.rbuf_is_mapped());
+    assert_eq!(response, &large[..]);
+}
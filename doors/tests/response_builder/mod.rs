@@ -0,0 +1,31 @@
+use doors::server::{Request, Response};
+use doors::testing::channel;
+use std::io;
+
+#[doors::server_procedure]
+fn describe(x: Request<'_>) -> Response<Vec<u8>> {
+    let built = Response::build(|w| {
+        let text = std::str::from_utf8(x.data)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        write!(w, "{} bytes: ", text.len())?;
+        for word in text.split_whitespace().rev() {
+            write!(w, "{} ", word)?;
+        }
+        Ok(())
+    });
+    built.unwrap_or_else(|e| Response::err_bytes(1, e.to_string().as_bytes()))
+}
+
+#[test]
+fn responses_can_be_written() {
+    let (_door, describe) = channel(describe).unwrap();
+    let response = describe.call_with_data(b"over the door").unwrap();
+    assert_eq!(response, "13 bytes: door the over ");
+}
+
+#[test]
+fn writer_errors_are_passed_along() {
+    let (_door, describe) = channel(describe).unwrap();
+    let response = describe.call_with_data(&[0xff, 0xfe]).unwrap();
+    assert_eq!(response.as_result().unwrap_err().code, 1);
+}
@@ -0,0 +1,106 @@
+use doors::server::{Request, Response};
+use doors::testing::channel;
+use doors::PoolStats;
+use std::fs::File;
+use std::os::fd::AsRawFd;
+use std::os::fd::IntoRawFd;
+
+/// Answer with 64KiB of the first byte of the request.
+#[doors::server_procedure]
+fn flood(x: Request<'_>) -> Response<Vec<u8>> {
+    Response::new(vec![x.data[0]; 64 * 1024])
+}
+
+/// Answer like `flood`, along with a descriptor for `/dev/null`.
+#[doors::server_procedure]
+fn flood_with_file(x: Request<'_>) -> Response<Vec<u8>> {
+    let null = File::open("/dev/null").unwrap();
+    Response::new(vec![x.data[0]; 64 * 1024])
+        .add_descriptor(null.into_raw_fd(), true)
+}
+
+#[test]
+fn pooled_regions_are_reused() {
+    let (_door, client) = channel(flood).unwrap();
+    let client = client.with_response_pool(2);
+
+    for i in 0..100u8 {
+        let response = client.call_with_data(&[i]).unwrap();
+        assert_eq!(response.data().len(), 64 * 1024);
+        assert!(response.data().iter().all(|&b| b == i));
+    }
+    let stats = client.stats();
+    assert_eq!(
+        stats,
+        PoolStats {
+            mapped: 1,
+            reused: 99
+        }
+    );
+}
+
+#[test]
+fn overlapping_responses_use_separate_regions() {
+    let (_door, client) = channel(flood).unwrap();
+    let client = client.with_response_pool(2);
+
+    for _ in 0..10 {
+        let first = client.call_with_data(&[1]).unwrap();
+        let second = client.call_with_data(&[2]).unwrap();
+        assert!(first.data().iter().all(|&b| b == 1));
+        assert!(second.data().iter().all(|&b| b == 2));
+    }
+    let stats = client.stats();
+    assert_eq!(
+        stats,
+        PoolStats {
+            mapped: 2,
+            reused: 18
+        }
+    );
+}
+
+#[test]
+fn empty_pools_map_every_time() {
+    let (_door, client) = channel(flood).unwrap();
+    let client = client.with_response_pool(0);
+
+    for i in 0..5u8 {
+        let response = client.call_with_data(&[i]).unwrap();
+        assert_eq!(response.data()[0], i);
+    }
+    assert_eq!(
+        client.stats(),
+        PoolStats {
+            mapped: 5,
+            reused: 0
+        }
+    );
+}
+
+#[test]
+fn pooled_responses_hand_over_their_descriptors() {
+    let (_door, client) = channel(flood_with_file).unwrap();
+    let client = client.with_response_pool(1);
+
+    for i in 0..3u8 {
+        let response = client.call_with_data(&[i]).unwrap();
+        assert!(response.data().iter().all(|&b| b == i));
+        let fds: Vec<_> = response.into_descriptors().collect();
+        assert_eq!(fds.len(), 1);
+
+        let raw = fds[0].as_raw_fd();
+        assert_ne!(unsafe { libc::fcntl(raw, libc::F_GETFD) }, -1);
+        drop(fds);
+        assert_eq!(unsafe { libc::fcntl(raw, libc::F_GETFD) }, -1);
+    }
+
+    // Taking the descriptors still gave each region back to the pool.
+    assert_eq!(
+        client.stats(),
+        PoolStats {
+            mapped: 1,
+            reused: 2
+        }
+    );
+}
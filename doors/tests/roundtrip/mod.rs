@@ -0,0 +1,58 @@
+use doors::illumos::DoorFd;
+use doors::server::{Request, Response};
+use doors::testing::channel;
+use doors::DoorArgument;
+use proptest::prelude::*;
+use std::fs::File;
+use std::os::fd::AsRawFd;
+
+/// The response buffer every call is made with, so that payloads can be
+/// generated on either side of it.
+const RBUF: usize = 256;
+
+#[doors::server_procedure]
+fn echo(x: Request<'_>) -> Response<Vec<u8>> {
+    // Send back what we were sent, closing our copies on the way out.
+    let mut response = Response::new(x.data.to_vec());
+    for d in x.descriptors {
+        let fd = unsafe { d.d_data.d_desc.d_descriptor };
+        response = response.add_descriptor(fd, true);
+    }
+    response
+}
+
+/// Payloads of every interesting size: empty, just short of the response
+/// buffer, exactly filling it, just over it, and far over it.
+fn payload() -> impl Strategy<Value = Vec<u8>> {
+    let len = prop_oneof![
+        Just(0),
+        Just(RBUF - 1),
+        Just(RBUF),
+        Just(RBUF + 1),
+        1..RBUF,
+        RBUF..64 * 1024,
+    ];
+    len.prop_flat_map(|len| proptest::collection::vec(any::<u8>(), len))
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn responses_echo_requests(data in payload(), count in 0..4usize) {
+        let (_door, client) = channel(echo).unwrap();
+        let null = File::open("/dev/null").unwrap();
+        let sent: Vec<_> =
+            (0..count).map(|_| DoorFd::new(null.as_raw_fd(), false)).collect();
+        let mut rbuf = [0; RBUF];
+        let arg = DoorArgument::new(&data, &sent, &mut rbuf);
+        let response = client.call(arg).unwrap();
+
+        // Data which could not fit the buffer came back in a mapped region.
+        if data.len() > RBUF {
+            prop_assert!(response.rbuf_is_mapped());
+        }
+        prop_assert_eq!(response.data(), &data[..]);
+        prop_assert_eq!(response.into_descriptors().count(), count);
+    }
+}
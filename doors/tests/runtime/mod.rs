@@ -0,0 +1,83 @@
+use doors::runtime::{AsyncDoor, AsyncRequest, AsyncServe};
+use doors::server::Response;
+use doors::Client;
+use doors::DoorError;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// Echo each request after a pause, counting the calls, and how many were in
+/// progress at once.
+#[derive(Default)]
+struct Sleepy {
+    calls: AtomicUsize,
+    in_flight: AtomicUsize,
+    peak: AtomicUsize,
+}
+
+impl AsyncServe for Sleepy {
+    async fn serve(
+        self: Arc<Self>,
+        request: AsyncRequest,
+    ) -> Response<Vec<u8>> {
+        let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        self.peak.fetch_max(in_flight, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        self.in_flight.fetch_sub(1, Ordering::SeqCst);
+        if request.data == b"panic" {
+            panic!("asked to");
+        }
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        Response::new(request.data)
+    }
+}
+
+fn sleepy_door(
+    runtime: &tokio::runtime::Runtime,
+    path: &str,
+) -> AsyncDoor<Sleepy> {
+    let door = AsyncDoor::new(Sleepy::default(), runtime.handle().clone());
+    let door = door.unwrap();
+    door.door().force_install(path).unwrap();
+    door
+}
+
+#[test]
+fn async_handlers_answer_door_calls() {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let door = sleepy_door(&runtime, "/tmp/runtime_answer.door");
+    let client = Client::open("/tmp/runtime_answer.door").unwrap();
+    assert_eq!(client.call_with_data(b"hello").unwrap(), "hello");
+    assert_eq!(door.state().calls.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn calls_wait_on_the_runtime_concurrently() {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let door = sleepy_door(&runtime, "/tmp/runtime_concurrent.door");
+    let callers: Vec<_> = (0..8)
+        .map(|_| {
+            let client = Client::open("/tmp/runtime_concurrent.door").unwrap();
+            thread::spawn(move || {
+                client.call_with_data(b"hello").unwrap().data().to_vec()
+            })
+        })
+        .collect();
+    for caller in callers {
+        assert_eq!(caller.join().unwrap(), b"hello");
+    }
+    assert_eq!(door.state().calls.load(Ordering::SeqCst), 8);
+    assert!(door.state().peak.load(Ordering::SeqCst) > 1);
+}
+
+#[test]
+fn async_panics_are_reported() {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let _door = sleepy_door(&runtime, "/tmp/runtime_panic.door");
+    let client = Client::open("/tmp/runtime_panic.door").unwrap();
+    let response = client.call_with_data(b"panic").unwrap();
+    let error = response.as_result().unwrap_err();
+    assert_eq!(error.code, DoorError::SERVER_PANICKED);
+    assert_eq!(error.detail, b"asked to");
+}
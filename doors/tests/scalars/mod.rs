@@ -0,0 +1,42 @@
+use doors::server::{Request, Response};
+use doors::testing::channel;
+
+#[doors::server_procedure]
+fn count(x: Request<'_>) -> Response<[u8; 4]> {
+    Response::scalar(x.data.len() as u32)
+}
+
+#[doors::server_procedure]
+fn total(x: Request<'_>) -> Response<[u8; 8]> {
+    Response::scalar_u64(x.data.iter().map(|&b| b as u64).sum())
+}
+
+#[doors::server_procedure]
+fn first(x: Request<'_>) -> Response<[u8; 1]> {
+    Response::scalar_u8(x.data[0])
+}
+
+#[test]
+fn response_sizes_match_the_scalars() {
+    assert_eq!(COUNT_RESPONSE_SIZE, 4);
+    assert_eq!(TOTAL_RESPONSE_SIZE, 8);
+    assert_eq!(FIRST_RESPONSE_SIZE, 1);
+}
+
+#[test]
+fn scalars_round_trip() {
+    let (_door, count) = channel(count).unwrap();
+    let response = count.call_with_data(&[0; 300]).unwrap();
+    assert_eq!(response, 300u32.to_le_bytes());
+    assert_eq!(response.as_u32(), Some(300));
+    assert_eq!(response.as_u64(), None);
+
+    let (_door, total) = channel(total).unwrap();
+    let response = total.call_with_data(&[255; 3]).unwrap();
+    assert_eq!(response.as_u64(), Some(765));
+
+    let (_door, first) = channel(first).unwrap();
+    let response = first.call_with_data(b"door").unwrap();
+    assert_eq!(response.as_u8(), Some(b'd'));
+    assert_eq!(response.as_u32(), None);
+}
@@ -0,0 +1,30 @@
+use doors::server::{Request, Response};
+use doors::testing::channel;
+
+#[doors::server_procedure]
+fn fragile(x: Request<'_>) -> Response<[u8; 1]> {
+    Response::new([x.data[0]])
+}
+
+#[doors::server_procedure(zeroize)]
+fn fragile_secret(x: Request<'_>) -> Response<[u8; 1]> {
+    Response::new([x.data[0]])
+}
+
+#[test]
+fn panics_are_reported_to_the_client() {
+    for procedure in [fragile, fragile_secret] {
+        let (_door, client) = channel(procedure).unwrap();
+
+        // Indexing an empty request panics.
+        let response = client.call_with_data(&[]).unwrap();
+        let error = response.as_result().unwrap_err();
+        assert!(error.is_server_panic());
+        let message = String::from_utf8(error.detail).unwrap();
+        assert!(message.contains("index out of bounds"));
+
+        // The door is still being served.
+        let response = client.call_with_data(&[7]).unwrap();
+        assert_eq!(response, [7]);
+    }
+}
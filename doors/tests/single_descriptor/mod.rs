@@ -0,0 +1,36 @@
+use doors::illumos::DoorFd;
+use doors::server::{Request, Response};
+use doors::testing::channel;
+use std::fs::File;
+use std::io::Read;
+use std::os::fd::{AsRawFd, FromRawFd};
+
+#[doors::server_procedure]
+fn open_files(x: Request<'_>) -> Response<[u8; 0]> {
+    Response::descriptors_only(x.data.iter().map(|_| {
+        let passwd = File::open("/etc/passwd").unwrap();
+        DoorFd::from_owned(passwd.into())
+    }))
+}
+
+#[test]
+fn one_descriptor_is_single() {
+    let (_door, client) = channel(open_files).unwrap();
+    let response = client.call_with_data(&[1]).unwrap();
+    let fd = response.single_descriptor().unwrap().as_raw_fd();
+    let mut passwd = unsafe { File::from_raw_fd(fd) };
+    let mut text = String::new();
+    passwd.read_to_string(&mut text).unwrap();
+    assert!(text.contains("root"));
+}
+
+#[test]
+fn none_or_several_descriptors_are_not_single() {
+    let (_door, client) = channel(open_files).unwrap();
+    let response = client.call_with_data(&[]).unwrap();
+    assert!(response.single_descriptor().is_none());
+
+    let response = client.call_with_data(&[1, 2]).unwrap();
+    assert!(response.single_descriptor().is_none());
+    assert_eq!(response.into_descriptors().count(), 2);
+}
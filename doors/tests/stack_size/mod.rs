@@ -0,0 +1,35 @@
+use doors::server::{Door, Request, Response};
+use doors::testing::TempDoor;
+
+#[doors::server_procedure]
+fn count(x: Request<'_>) -> Response<[u8; 4]> {
+    Response::scalar(x.data.len() as u32)
+}
+
+#[test]
+fn large_stacks_take_large_requests() {
+    let door = Door::builder()
+        .stack_size(16 * 1024 * 1024)
+        .build(count)
+        .unwrap();
+    assert!(door.info().unwrap().is_private());
+    let door = TempDoor::new(door).unwrap();
+    let client = door.client().unwrap();
+    let request = vec![0; 4 * 1024 * 1024];
+    let response = client.call_with_data(&request).unwrap();
+    assert_eq!(response, (request.len() as u32).to_le_bytes());
+}
+
+#[doors::server_procedure]
+fn whoami(_x: Request<'_>) -> Response<Vec<u8>> {
+    let name = std::thread::current().name().unwrap_or("").to_string();
+    Response::new(name.into_bytes())
+}
+
+#[test]
+fn door_threads_can_be_named() {
+    let door = Door::builder().thread_name("whoami-door").build(whoami);
+    let door = TempDoor::new(door.unwrap()).unwrap();
+    let client = door.client().unwrap();
+    assert_eq!(client.call_with_data(&[]).unwrap(), "whoami-door");
+}
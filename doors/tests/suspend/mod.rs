@@ -0,0 +1,39 @@
+use doors::server::{Door, Gate, Request, Response};
+use doors::testing::TempDoor;
+use doors::{DoorCallError, DoorError};
+
+#[doors::server_procedure(state = Gate)]
+fn echo(x: Request<'_, Gate>) -> Response<Vec<u8>> {
+    if !x.state.is_open() {
+        return Response::unavailable();
+    }
+    Response::new(x.data.to_vec())
+}
+
+#[test]
+fn suspended_doors_turn_calls_away() {
+    let gate: &'static Gate = Box::leak(Box::default());
+    let door =
+        TempDoor::new(Door::create_with_state(echo, gate).unwrap()).unwrap();
+    let early = door.client().unwrap();
+
+    door.door().suspend(door.path(), gate).unwrap();
+    assert!(!gate.is_open());
+
+    // Clients which already had the door open are told to come back later.
+    let response = early.call_with_data(b"hello").unwrap();
+    assert_eq!(
+        response.as_result().unwrap_err().code,
+        DoorError::UNAVAILABLE
+    );
+
+    // New clients only find the empty jamb.
+    let late = door.client().unwrap();
+    let result = late.call_with_data(b"hello");
+    assert_eq!(result.unwrap_err(), DoorCallError::NotADoor);
+
+    door.door().resume(door.path(), gate).unwrap();
+    assert_eq!(early.call_with_data(b"hello").unwrap(), b"hello");
+    let late = door.client().unwrap();
+    assert_eq!(late.call_with_data(b"again").unwrap(), b"again");
+}
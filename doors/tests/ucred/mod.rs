@@ -0,0 +1,63 @@
+use doors::server::{Request, Response};
+use doors::testing::channel;
+
+/// Answer with the caller's pid, zone and session, or nothing at all.
+#[doors::server_procedure]
+fn whoami(x: Request<'_>) -> Response<Vec<u8>> {
+    let ucred = match x.ucred() {
+        Ok(ucred) => ucred,
+        Err(_) => return Response::new(vec![]),
+    };
+    let mut who = vec![];
+    for field in [ucred.pid(), ucred.zone_id(), ucred.session_id()] {
+        who.extend_from_slice(&field.unwrap_or(-1).to_le_bytes());
+    }
+    Response::new(who)
+}
+
+#[test]
+fn servers_see_their_clients_credentials() {
+    let (_door, whoami) = channel(whoami).unwrap();
+    let response = whoami.call_with_data(&[]).unwrap();
+    let field = |i: usize| {
+        let bytes = response.data()[i * 4..(i + 1) * 4].try_into().unwrap();
+        i32::from_le_bytes(bytes)
+    };
+
+    assert_eq!(response.data().len(), 12);
+    assert_eq!(field(0), std::process::id() as i32);
+    assert_eq!(field(1), unsafe { getzoneid() });
+    assert_ne!(field(2), -1);
+}
+
+/// Answer with the identity of the caller, or nothing at all.
+#[doors::server_procedure]
+fn identify(x: Request<'_>) -> Response<Vec<u8>> {
+    let identity = match x.ucred().ok().and_then(|ucred| ucred.identity()) {
+        Some(identity) => identity,
+        None => return Response::new(vec![]),
+    };
+    let mut who = identity.pid.to_le_bytes().to_vec();
+    for field in [identity.zone_id, identity.session_id] {
+        who.extend_from_slice(&field.unwrap_or(-1).to_le_bytes());
+    }
+    Response::new(who)
+}
+
+#[test]
+fn identities_combine_the_identifying_fields() {
+    let (_door, identify) = channel(identify).unwrap();
+    let (_door, whoami) = channel(whoami).unwrap();
+    let identity = identify.call_with_data(&[]).unwrap();
+    let fields = whoami.call_with_data(&[]).unwrap();
+
+    assert_eq!(identity.data().len(), 12);
+    assert_eq!(identity.data(), fields.data());
+
+    // The same process is the same client, call after call.
+    assert_eq!(identify.call_with_data(&[]).unwrap(), identity.data());
+}
+
+extern "C" {
+    fn getzoneid() -> libc::c_int;
+}
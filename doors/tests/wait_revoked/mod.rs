@@ -0,0 +1,28 @@
+use doors::server::{Request, Response};
+use doors::testing::channel;
+use std::time::{Duration, Instant};
+
+#[doors::server_procedure]
+fn nothing(_x: Request<'_>) -> Response<[u8; 0]> {
+    Response::empty()
+}
+
+#[test]
+fn clients_see_their_door_revoked() {
+    let (door, client) = channel(nothing).unwrap();
+    let start = Instant::now();
+    let revoker = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(50));
+        drop(door);
+    });
+    client.wait_revoked(Duration::from_millis(5));
+    assert!(start.elapsed() >= Duration::from_millis(50));
+    revoker.join().unwrap();
+}
+
+#[test]
+fn revoked_doors_are_not_waited_on() {
+    let (door, client) = channel(nothing).unwrap();
+    drop(door);
+    client.wait_revoked(Duration::from_secs(60));
+}
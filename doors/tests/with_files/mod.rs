@@ -0,0 +1,40 @@
+use doors::server::{Request, Response};
+use doors::testing::channel;
+use doors::DoorArgument;
+use std::fs::File;
+use std::io::Read;
+use std::os::fd::FromRawFd;
+
+const FIRST: &str = "/tmp/with_files_first.txt";
+const SECOND: &str = "/tmp/with_files_second.txt";
+
+#[doors::server_procedure]
+fn concatenate(x: Request<'_>) -> Response<Vec<u8>> {
+    let mut text = Vec::new();
+    for d in x.descriptors {
+        let fd = unsafe { d.d_data.d_desc.d_descriptor };
+        let mut file = unsafe { File::from_raw_fd(fd) };
+        file.read_to_end(&mut text).unwrap();
+    }
+    Response::new(text)
+}
+
+#[test]
+fn borrowed_files_are_sent() {
+    std::fs::write(FIRST, "door ").unwrap();
+    std::fs::write(SECOND, "hinge").unwrap();
+    let first = File::open(FIRST).unwrap();
+    let second = File::open(SECOND).unwrap();
+    let (_door, client) = channel(concatenate).unwrap();
+
+    let mut rbuf = [0; 64];
+    let files = [&first, &second];
+    let arg = DoorArgument::with_files(&[], &files, &[false, true], &mut rbuf);
+    let response = client.call_with_files(arg.unwrap()).unwrap();
+    assert_eq!(response, "door hinge");
+
+    // The released file was sent as a duplicate, so it is still open, and
+    // shares the offset the server read it up to.
+    let mut rest = String::new();
+    assert_eq!((&second).read_to_string(&mut rest).unwrap(), 0);
+}
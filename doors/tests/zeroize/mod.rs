@@ -0,0 +1,40 @@
+use doors::server::{Door, Request, Response};
+use doors::Client;
+use std::os::fd::AsRawFd;
+use std::os::fd::FromRawFd;
+
+#[doors::server_procedure(zeroize)]
+fn reverse(x: Request<'_>) -> Response<Vec<u8>> {
+    let mut secret = x.data.to_vec();
+    secret.reverse();
+    Response::new(secret)
+}
+
+#[doors::server_procedure(zeroize = 4096)]
+fn echo(x: Request<'_>) -> Response<&[u8]> {
+    Response::new(x.data)
+}
+
+fn client_for(door: &Door) -> Client {
+    let fd = unsafe { libc::dup(door.as_raw_fd()) };
+    assert_ne!(fd, -1);
+    unsafe { Client::from_raw_fd(fd) }
+}
+
+#[test]
+fn zeroized_procedures_still_answer() {
+    let door = Door::create(reverse).unwrap();
+    let client = client_for(&door);
+
+    let response = client.call_with_data(b"hunter2").unwrap();
+    assert_eq!(response, b"2retnuh");
+}
+
+#[test]
+fn zeroize_leaves_responses_borrowed_from_the_request_alone() {
+    let door = Door::create(echo).unwrap();
+    let client = client_for(&door);
+
+    let response = client.call_with_data(b"hunter2").unwrap();
+    assert_eq!(response, b"hunter2");
+}
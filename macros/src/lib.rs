@@ -10,8 +10,173 @@
 
 use proc_macro::TokenStream;
 use quote::{format_ident, quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
 use syn::spanned::Spanned;
-use syn::{parse_macro_input, Error, FnArg, ItemFn, Pat, ReturnType};
+use syn::{
+    parse_macro_input, Attribute, Data, DeriveInput, Error, Expr, FnArg,
+    GenericArgument, Ident, ItemFn, LitStr, Meta, NestedMeta, Pat,
+    PathArguments, ReturnType, Token, Type,
+};
+
+/// Options accepted by [`macro@server_procedure`], e.g.
+/// `#[server_procedure(state = MyState)]`.
+#[derive(Default)]
+struct Options {
+    state: Option<Type>,
+    /// How much stack to scrub, if any.
+    zeroize: Option<Expr>,
+    /// Where the generated installer puts the door, if anywhere.
+    install: Option<LitStr>,
+    /// Whether the handler is handed the client's credentials.
+    authorize: bool,
+    /// Whether the handler sees unreferenced notifications.
+    unref: bool,
+}
+
+impl Parse for Options {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut options = Options::default();
+        while !input.is_empty() {
+            let key: Ident = input.parse()?;
+            match key.to_string().as_str() {
+                "state" => {
+                    input.parse::<Token![=]>()?;
+                    options.state = Some(input.parse()?);
+                }
+                "authorize" => options.authorize = true,
+                "unref" => options.unref = true,
+                "install" => {
+                    input.parse::<Token![=]>()?;
+                    options.install = Some(input.parse()?);
+                }
+                "zeroize" => {
+                    options.zeroize = Some(if input.peek(Token![=]) {
+                        input.parse::<Token![=]>()?;
+                        Expr::Lit(input.parse()?)
+                    } else {
+                        syn::parse_quote! { doors::server::ZEROIZE_DEPTH }
+                    });
+                }
+                _ => {
+                    return Err(Error::new(
+                        key.span(),
+                        "unknown server_procedure option",
+                    ))
+                }
+            }
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+        Ok(options)
+    }
+}
+
+/// The name and type of a handler's only argument.
+fn single_argument(input: &ItemFn) -> syn::Result<(Ident, Type)> {
+    // check number of arguments, we only support a single argument
+    if input.sig.inputs.len() != 1 {
+        return Err(Error::new(
+            input.sig.inputs.span(),
+            "doors should take a single Request as input",
+        ));
+    }
+
+    // extract the single argument and it's type
+    let arg = &input.sig.inputs[0];
+    match arg {
+        FnArg::Receiver(_) => Err(Error::new(
+            arg.span(),
+            "only standalone functions supported",
+        )),
+
+        FnArg::Typed(pt) => match &*pt.pat {
+            Pat::Ident(i) => Ok((i.ident.clone(), *pt.ty.clone())),
+            _ => Err(Error::new(
+                arg.span(),
+                "only identifier arguments supported",
+            )),
+        },
+    }
+}
+
+/// The names and types of an authorizing handler's two arguments: the request
+/// and the client's credentials.
+fn authorized_arguments(
+    input: &ItemFn,
+) -> syn::Result<((Ident, Type), (Ident, Type))> {
+    let mut arguments = vec![];
+    for arg in &input.sig.inputs {
+        match arg {
+            FnArg::Typed(pt) => match &*pt.pat {
+                Pat::Ident(i) => {
+                    arguments.push((i.ident.clone(), *pt.ty.clone()))
+                }
+                _ => {
+                    return Err(Error::new(
+                        arg.span(),
+                        "only identifier arguments supported",
+                    ))
+                }
+            },
+            FnArg::Receiver(_) => {
+                return Err(Error::new(
+                    arg.span(),
+                    "only standalone functions supported",
+                ))
+            }
+        }
+    }
+    match <[_; 2]>::try_from(arguments) {
+        Ok([request, who]) if is_named(&who.1, "Ucred") => Ok((request, who)),
+        Ok([_, who]) => Err(Error::new(
+            who.1.span(),
+            "the second argument of an authorizing door must be a Ucred",
+        )),
+        Err(_) => Err(Error::new(
+            input.sig.inputs.span(),
+            "authorizing doors should take a Request and a Ucred as input",
+        )),
+    }
+}
+
+/// Whether `ty` names `name`, however it is qualified.
+fn is_named(ty: &Type, name: &str) -> bool {
+    match ty {
+        Type::Path(p) => p
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == name),
+        _ => false,
+    }
+}
+
+/// Whether `ty` names `Request`, however it is qualified.
+fn is_request(ty: &Type) -> bool {
+    is_named(ty, "Request")
+}
+
+/// The length of the array in `Response<[u8; N]>`, if that is what `output` is.
+fn response_size(output: &ReturnType) -> Option<&Expr> {
+    let segment = match output {
+        ReturnType::Type(_, t) => match &**t {
+            Type::Path(p) => p.path.segments.last()?,
+            _ => return None,
+        },
+        ReturnType::Default => return None,
+    };
+    if segment.ident != "Response" {
+        return None;
+    }
+    match &segment.arguments {
+        PathArguments::AngleBracketed(args) => match args.args.first()? {
+            GenericArgument::Type(Type::Array(array)) => Some(&array.len),
+            _ => None,
+        },
+        _ => None,
+    }
+}
 
 /// This macro transforms a Rust function into a Doors-compatible server
 /// procedure.
@@ -28,52 +193,221 @@ use syn::{parse_macro_input, Error, FnArg, ItemFn, Pat, ReturnType};
 ///     todo!();
 /// }
 /// ```
+///
+/// The argument must be a `doors::server::Request`, which is what the generated
+/// code passes to the handler. Anything else is refused, pointing at the
+/// argument's type:
+///
+/// ```compile_fail
+/// use doors::server::Response;
+///
+/// #[doors::server_procedure]
+/// fn double(x: u8) -> Response<[u8; 1]> {
+///     Response::new([x * 2])
+/// }
+/// ```
+///
+/// ## Typed State
+///
+/// A door's cookie can point to some state shared by every invocation. Name
+/// the type of that state with `state = ...`, and the handler will find a
+/// reference to it in `Request::state`. The procedure then becomes a
+/// `StatefulProcedure`, which can only be turned into a door by
+/// `Door::create_with_state` with a state of the matching type -- passing it
+/// to `Door::create`, or any other constructor that would hand it a plain
+/// integer cookie, is a compile error.
+///
+/// ```
+/// use doors::server::Door;
+/// use doors::server::Request;
+/// use doors::server::Response;
+/// use std::sync::atomic::{AtomicU8, Ordering};
+///
+/// struct Counter(AtomicU8);
+///
+/// #[doors::server_procedure(state = Counter)]
+/// fn increment(x: Request<'_, Counter>) -> Response<[u8; 1]> {
+///     Response::new([x.state.0.fetch_add(1, Ordering::SeqCst)])
+/// }
+///
+/// static COUNTER: Counter = Counter(AtomicU8::new(0));
+/// let door = Door::create_with_state(increment, &COUNTER).unwrap();
+/// ```
+///
+/// ## Panics
+///
+/// A panic must not unwind out of a server procedure, so the handler runs
+/// under `std::panic::catch_unwind`. If it panics, the client is sent an error
+/// frame whose code is `doors::DoorError::SERVER_PANICKED` and whose detail is
+/// the panic message, and the door thread carries on serving other calls.
+///
+/// ## Unreferenced Notifications
+///
+/// A door created with `DoorAttributes::unref` or `DoorAttributes::unref_multi`
+/// has its procedure invoked, with no data, whenever it runs out of clients.
+/// Nobody is waiting for an answer, and a handler written for calls may not
+/// expect an empty request, so by default the wrapper answers notifications
+/// itself, without invoking the handler. With `unref`, the handler sees them
+/// too, and should tell them apart with `doors::server::Request::is_unref`.
+///
+/// ```
+/// use doors::server::Request;
+/// use doors::server::Response;
+/// use std::sync::atomic::{AtomicBool, Ordering};
+///
+/// static IDLE: AtomicBool = AtomicBool::new(false);
+///
+/// #[doors::server_procedure(unref)]
+/// fn double(x: Request<'_>) -> Response<[u8; 1]> {
+///     if x.is_unref() {
+///         IDLE.store(true, Ordering::SeqCst);
+///         return Response::new([0]);
+///     }
+///     Response::new([x.data[0] * 2])
+/// }
+/// ```
+///
+/// ## Returning Once
+///
+/// A door invocation must be answered exactly once. The wrapper answers with
+/// whatever the handler returns, unless the handler answers early with
+/// `doors::server::Request::reply`, which never returns. What a handler must
+/// not do is call `door_return` itself, for instance with
+/// `doors::server::Response::door_return`: that answers without closing the
+/// `doors::server::Invocation` the wrapper opened. In debug builds, the
+/// process then aborts with a message saying so.
+///
+/// ## Response Size
+///
+/// A client whose response buffer is too small gets its response in a region
+/// the kernel maps just for the occasion, which is slower. When a handler
+/// returns a fixed-size `Response<[u8; N]>`, the macro also emits a constant
+/// named after it, with the same visibility, holding `N`, so that clients in
+/// the same crate can size their buffers to match:
+///
+/// ```
+/// use doors::server::Request;
+/// use doors::server::Response;
+///
+/// #[doors::server_procedure]
+/// fn double(x: Request<'_>) -> Response<[u8; 1]> {
+///     Response::new([x.data[0] * 2])
+/// }
+///
+/// let rbuf = [0u8; DOUBLE_RESPONSE_SIZE];
+/// ```
+///
+/// ## Zeroize
+///
+/// Door threads are reused, so whatever a handler leaves on its stack is still
+/// there when the next client's call lands on the same thread. For handlers
+/// which touch secrets, `zeroize` runs the handler in a frame of its own and,
+/// once it has produced a response, wipes the stack that frame occupied along
+/// with the request data (unless the response points into it). The response
+/// itself is left alone, since the kernel has yet to copy it out. The depth
+/// scrubbed defaults to `doors::server::ZEROIZE_DEPTH` bytes, and can be set
+/// with `zeroize = 65536` for handlers with deeper stacks. See
+/// `doors::server::zeroize` for details.
+///
+/// ```
+/// use doors::server::Request;
+/// use doors::server::Response;
+///
+/// #[doors::server_procedure(zeroize)]
+/// fn check_password(x: Request<'_>) -> Response<[u8; 1]> {
+///     Response::new([(x.data == b"hunter2") as u8])
+/// }
+/// ```
+///
+/// ## Install
+///
+/// Most servers create a door and install it at a fixed path before doing
+/// anything else. With `install = "/path/to.door"`, the macro also emits a
+/// function named `install_` followed by the handler's name, with the same
+/// visibility, which does both: it creates the door, force-installs it at the
+/// path (replacing whatever was there), and returns it. With typed state, the
+/// installer takes the `&'static` state to create the door with.
+///
+/// ```no_run
+/// use doors::server::Request;
+/// use doors::server::Response;
+///
+/// #[doors::server_procedure(install = "/tmp/double.door")]
+/// fn double(x: Request<'_>) -> Response<[u8; 1]> {
+///     Response::new([x.data[0] * 2])
+/// }
+///
+/// fn main() {
+///     let _door = install_double().unwrap();
+///     std::thread::park();
+/// }
+/// ```
+///
+/// ## Authorize
+///
+/// Services which authorize every call need to know who is calling before
+/// anything else. With `authorize`, the handler takes a second argument, a
+/// `doors::illumos::Ucred`, holding the client's credentials, fetched before
+/// the handler is invoked. Should they be unavailable, the handler is never
+/// invoked, and the client is answered with an error frame whose code is
+/// `doors::DoorError::NO_CREDENTIALS`.
+///
+/// ```
+/// use doors::illumos::Ucred;
+/// use doors::server::Request;
+/// use doors::server::Response;
+///
+/// #[doors::server_procedure(authorize)]
+/// fn root_only(x: Request<'_>, who: Ucred) -> Response<[u8; 1]> {
+///     match who.euid() {
+///         Some(0) => Response::new([1]),
+///         _ => Response::new([0]),
+///     }
+/// }
+/// ```
 #[proc_macro_attribute]
-pub fn server_procedure(_attr: TokenStream, item: TokenStream) -> TokenStream {
+pub fn server_procedure(attr: TokenStream, item: TokenStream) -> TokenStream {
+    // parse any options given to the attribute
+    let options = parse_macro_input!(attr as Options);
+
     // parse the function this attribute was applied to
     let input = parse_macro_input!(item as ItemFn);
 
     // extract the function name
     let name = format_ident!("{}", input.sig.ident.to_string());
 
-    // check number of arguments, we only support a single argument
-    if input.sig.inputs.len() != 1 {
+    // extract the single argument and it's type, and the credentials too if
+    // the handler is to authorize its callers
+    let arguments = match options.authorize {
+        true => authorized_arguments(&input).map(|(arg, who)| (arg, Some(who))),
+        false => single_argument(&input).map(|arg| (arg, None)),
+    };
+    let ((arg_ident, arg_type), who) = match arguments {
+        Ok(arguments) => arguments,
+        Err(e) => return e.to_compile_error().into(),
+    };
+
+    // the generated code hands the handler a Request, so say so here rather
+    // than with a type mismatch somewhere inside the expansion
+    if !is_request(&arg_type) {
         return Error::new(
-            input.sig.inputs.span(),
-            "doors should take a single Request as input",
+            arg_type.span(),
+            "doors should take a single Request as input; \
+             see server_procedure_typed for plain values",
         )
         .to_compile_error()
         .into();
     }
 
-    // extract the single argument and it's type
-    let arg = &input.sig.inputs[0];
-    let (arg_ident, arg_type) = match arg {
-        FnArg::Receiver(_) => {
-            return Error::new(
-                arg.span(),
-                "only standalone functions supported",
-            )
-            .to_compile_error()
-            .into();
-        }
-
-        FnArg::Typed(pt) => {
-            let p = match &*pt.pat {
-                Pat::Ident(i) => i.ident.to_string(),
-
-                _ => {
-                    return Error::new(
-                        arg.span(),
-                        "only identifier arguments supported",
-                    )
-                    .to_compile_error()
-                    .into()
-                }
-            };
-            (format_ident!("{}", p), *pt.ty.clone())
-        }
-    };
+    // note the size of fixed-size responses, before the output is consumed
+    let size_const = response_size(&input.sig.output).map(|n| {
+        let vis = &input.vis;
+        let ident = format_ident!(
+            "{}_RESPONSE_SIZE",
+            input.sig.ident.to_string().to_uppercase()
+        );
+        quote! { #vis const #ident: usize = #n; }
+    });
 
     //extract the return type
     let return_type = match input.sig.output {
@@ -84,58 +418,447 @@ pub fn server_procedure(_attr: TokenStream, item: TokenStream) -> TokenStream {
     // extract the body of the function
     let blk = input.block;
 
+    // with typed state, the raw procedure hides behind a StatefulProcedure
+    let (proc_name, state) = match &options.state {
+        Some(ty) => (
+            format_ident!("__{}_server_procedure", name),
+            quote! { unsafe { &*(cookie as *const #ty) } },
+        ),
+        None => (name.clone(), quote! { &() }),
+    };
+
+    // with authorize, the client's credentials are fetched up front, and
+    // handed to the handler along with the request
+    let (fetch_who, who_param, who_arg, who_binding) = match &who {
+        Some((who_ident, who_type)) => (
+            quote! {
+                let who = match doors::illumos::door_ucred() {
+                    Ok(who) => who,
+                    Err(_) => {
+                        invocation.exit();
+                        doors::server::Response::err_bytes(
+                            doors::DoorError::NO_CREDENTIALS,
+                            b"could not read the client's credentials",
+                        )
+                        .door_return()
+                    }
+                };
+            },
+            quote! { , #who_ident: #who_type },
+            quote! { , who },
+            quote! { let #who_ident: #who_type = who; },
+        ),
+        None => (quote! {}, quote! {}, quote! {}, quote! {}),
+    };
+
+    // with zeroize, the handler gets a frame of its own so that it can be
+    // scrubbed once the response is in hand
+    let invoke = match &options.zeroize {
+        Some(depth) => quote! {
+            #[inline(never)]
+            fn handler(#arg_ident: #arg_type #who_param) -> #return_type #blk
+
+            let response = std::panic::catch_unwind(
+                std::panic::AssertUnwindSafe(|| handler(request #who_arg)),
+            );
+            invocation.exit();
+            match response {
+                Ok(response) => {
+                    unsafe {
+                        doors::server::zeroize::<{ #depth }, _>(
+                            argp as *mut u8,
+                            arg_size,
+                            &response,
+                        );
+                    }
+                    response.door_return()
+                }
+                Err(payload) => {
+                    let response =
+                        doors::server::Response::from_panic(payload);
+                    unsafe {
+                        doors::server::zeroize::<{ #depth }, _>(
+                            argp as *mut u8,
+                            arg_size,
+                            &response,
+                        );
+                    }
+                    response.door_return()
+                }
+            }
+        },
+        None => quote! {
+            let f = || -> #return_type {
+                let #arg_ident: #arg_type = request;
+                #who_binding
+                #blk
+            };
+
+            match std::panic::catch_unwind(std::panic::AssertUnwindSafe(f)) {
+                Ok(response) => {
+                    invocation.exit();
+                    response.door_return()
+                }
+                Err(payload) => {
+                    invocation.exit();
+                    doors::server::Response::from_panic(payload).door_return()
+                }
+            }
+        },
+    };
+
+    // unless the handler asked for them, unreferenced notifications are
+    // answered before it ever sees them
+    let skip_unref = match options.unref {
+        true => quote! {},
+        false => quote! {
+            if argp == doors::illumos::door_h::DOOR_UNREF_DATA {
+                doors::server::Response::<[u8; 0]>::empty().door_return()
+            }
+        },
+    };
+
     // generate the output function
-    let q = quote! {
+    let mut q = quote! {
 
-        extern "C" fn #name(
+        extern "C" fn #proc_name(
             cookie: *const std::os::raw::c_void,
             argp: *const std::os::raw::c_char,
             arg_size: usize,
             dp: *const doors::illumos::door_h::door_desc_t,
             n_desc: std::os::raw::c_uint,
          ) {
+            #skip_unref
 
-            let f = || -> #return_type {
-                let #arg_ident: #arg_type = doors::server::Request {
-                    data: unsafe {
-                        std::slice::from_raw_parts::<u8>(
-                            argp as *const u8,
-                            arg_size
-                        )
-                    },
-                    descriptors: unsafe {
-                        std::slice::from_raw_parts(
-                            dp,
-                            n_desc.try_into().unwrap()
-                        )
-                    },
-                    cookie: cookie as u64
-                };
-                #blk
+            let request = doors::server::Request {
+                data: unsafe {
+                    std::slice::from_raw_parts::<u8>(
+                        argp as *const u8,
+                        arg_size
+                    )
+                },
+                descriptors: unsafe {
+                    std::slice::from_raw_parts(
+                        dp,
+                        n_desc.try_into().unwrap()
+                    )
+                },
+                cookie: cookie as u64,
+                state: #state,
             };
+            let invocation = doors::server::Invocation::enter(
+                stringify!(#name),
+                &request,
+            );
 
-            let mut response = f();
-            match response.data {
-                Some(data) => unsafe {
-                    doors::illumos::door_h::door_return(
-                        data.as_ref().as_ptr() as *const std::os::raw::c_char,
-                        data.as_ref().len(),
-                        response.descriptors.as_ptr() as *const doors::illumos::door_h::door_desc_t,
-                        response.num_descriptors,
-                    )
+            #fetch_who
+            #invoke
+        }
+
+    };
+
+    q.extend(size_const);
+
+    if let Some(ty) = &options.state {
+        q.extend(quote! {
+            #[allow(non_upper_case_globals)]
+            const #name: doors::server::StatefulProcedure<#ty> = unsafe {
+                doors::server::StatefulProcedure::new(#proc_name)
+            };
+        });
+    }
+
+    if let Some(path) = &options.install {
+        let vis = &input.vis;
+        let installer = format_ident!("install_{}", name);
+        let (param, create) = match &options.state {
+            Some(ty) => (
+                quote! { state: &'static #ty },
+                quote! { doors::server::Door::create_with_state(#name, state) },
+            ),
+            None => (quote! {}, quote! { doors::server::Door::create(#name) }),
+        };
+        q.extend(quote! {
+            #vis fn #installer(
+                #param
+            ) -> ::std::result::Result<
+                doors::server::Door,
+                doors::server::Error,
+            > {
+                let door = #create?;
+                door.force_install(#path)?;
+                ::std::result::Result::Ok(door)
+            }
+        });
+    }
+
+    TokenStream::from(q)
+}
+
+/// This macro transforms a Rust function which takes and returns plain values
+/// into a Doors-compatible server procedure.
+///
+/// The argument type must implement `doors::typed::FromBytes`, and the return
+/// type `doors::typed::AsBytes`. The request is checked to be exactly the size
+/// of the argument type and copied out (so it need not be aligned); a request
+/// of any other size is answered with an error frame whose code is
+/// `doors::DoorError::WRONG_SIZE`, without calling the handler. Otherwise, the
+/// handler's result is sent back as its bytes. Panics are caught, and each
+/// call is wrapped in a `doors::server::Invocation`, as with
+/// [`macro@server_procedure`]. Clients call these with
+/// `doors::Client::call_typed`.
+///
+/// ```
+/// use doors::typed::{AsBytes, FromBytes};
+///
+/// #[derive(Clone, Copy)]
+/// #[repr(C)]
+/// struct Point {
+///     x: i32,
+///     y: i32,
+/// }
+///
+/// // Safety: two i32s, with no padding between them.
+/// unsafe impl AsBytes for Point {}
+/// unsafe impl FromBytes for Point {}
+///
+/// #[doors::server_procedure_typed]
+/// fn mirror(p: Point) -> Point {
+///     Point { x: p.y, y: p.x }
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn server_procedure_typed(
+    _attr: TokenStream,
+    item: TokenStream,
+) -> TokenStream {
+    // parse the function this attribute was applied to
+    let input = parse_macro_input!(item as ItemFn);
+    let name = &input.sig.ident;
+
+    let (arg_ident, arg_type) = match single_argument(&input) {
+        Ok(arg) => arg,
+        Err(e) => return e.to_compile_error().into(),
+    };
+    let return_type = match &input.sig.output {
+        ReturnType::Type(_, t) => t,
+        ReturnType::Default => {
+            return Error::new(
+                input.sig.span(),
+                "typed doors must return a value",
+            )
+            .to_compile_error()
+            .into()
+        }
+    };
+    let blk = &input.block;
+
+    let q = quote! {
+        extern "C" fn #name(
+            cookie: *const std::os::raw::c_void,
+            argp: *const std::os::raw::c_char,
+            arg_size: usize,
+            dp: *const doors::illumos::door_h::door_desc_t,
+            n_desc: std::os::raw::c_uint,
+        ) {
+            fn handler(#arg_ident: #arg_type) -> #return_type #blk
+
+            let data: &[u8] = match arg_size {
+                0 => &[],
+                _ => unsafe {
+                    std::slice::from_raw_parts(argp as *const u8, arg_size)
+                },
+            };
+            let descriptors = match n_desc {
+                0 => &[],
+                _ => unsafe {
+                    std::slice::from_raw_parts(dp, n_desc.try_into().unwrap())
                 },
-                None => unsafe {
-                    doors::illumos::door_h::door_return(
-                        std::ptr::null() as *const std::os::raw::c_char,
-                        0,
-                        response.descriptors.as_ptr() as *const doors::illumos::door_h::door_desc_t,
-                        response.num_descriptors,
+            };
+            let request = doors::server::Request {
+                data,
+                descriptors,
+                cookie: cookie as u64,
+                state: &(),
+            };
+            let invocation = doors::server::Invocation::enter(
+                stringify!(#name),
+                &request,
+            );
+
+            let arg = match <#arg_type as doors::typed::FromBytes>::from_bytes(data) {
+                Some(arg) => arg,
+                None => {
+                    invocation.exit();
+                    doors::server::Response::err_bytes(
+                        doors::DoorError::WRONG_SIZE,
+                        b"request is the wrong size",
                     )
+                    .door_return()
                 }
-            }
+            };
+            let handled = std::panic::catch_unwind(
+                std::panic::AssertUnwindSafe(|| handler(arg)),
+            );
+            let response = match handled {
+                Ok(result) => doors::server::Response::new(
+                    doors::typed::AsBytes::as_bytes(&result).to_vec(),
+                ),
+                Err(payload) => doors::server::Response::from_panic(payload),
+            };
+            invocation.exit();
+            response.door_return()
+        }
+    };
+
+    TokenStream::from(q)
+}
+
+/// Whether `#[repr(C)]` is among these attributes.
+fn is_repr_c(attrs: &[Attribute]) -> bool {
+    attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("repr"))
+        .any(|attr| match attr.parse_meta() {
+            Ok(Meta::List(list)) => list.nested.iter().any(|nested| {
+                matches!(nested, NestedMeta::Meta(Meta::Path(p)) if p.is_ident("C"))
+            }),
+            _ => false,
+        })
+}
+
+/// The part of `ty`, if any, which points somewhere in this process.
+fn find_pointer(ty: &Type) -> Option<&Type> {
+    match ty {
+        Type::Ptr(_) | Type::Reference(_) | Type::BareFn(_) => Some(ty),
+        Type::Array(array) => find_pointer(&array.elem),
+        Type::Group(group) => find_pointer(&group.elem),
+        Type::Paren(paren) => find_pointer(&paren.elem),
+        Type::Tuple(tuple) => tuple.elems.iter().find_map(find_pointer),
+        _ => None,
+    }
+}
+
+/// This derives `doors::typed::AsBytes` and `doors::typed::FromBytes` for a
+/// struct, so that it can be used with [`macro@server_procedure_typed`] and
+/// `doors::Client::call_typed`.
+///
+/// Those traits are unsafe to implement by hand, because the compiler cannot
+/// check what they promise. This derive checks it instead: the struct must be
+/// `#[repr(C)]`, so that its layout is fixed; every field must itself be a
+/// payload (integers, floats, arrays of them, and other `DoorPayload`
+/// structs); and the fields must add up to the size of the whole struct, so
+/// that there is no padding. Like the traits, it also needs `Clone` and `Copy`.
+///
+/// ```
+/// #[derive(Clone, Copy, doors::DoorPayload)]
+/// #[repr(C)]
+/// struct Point {
+///     x: i32,
+///     y: i32,
+/// }
+///
+/// #[doors::server_procedure_typed]
+/// fn mirror(p: Point) -> Point {
+///     Point { x: p.y, y: p.x }
+/// }
+/// ```
+///
+/// Structs without `#[repr(C)]` are refused, since Rust may lay them out
+/// however it likes:
+///
+/// ```compile_fail
+/// #[derive(Clone, Copy, doors::DoorPayload)]
+/// struct Point {
+///     x: i32,
+///     y: i32,
+/// }
+/// ```
+///
+/// So are pointers and references, which mean nothing in another process:
+///
+/// ```compile_fail
+/// #[derive(Clone, Copy, doors::DoorPayload)]
+/// #[repr(C)]
+/// struct Named {
+///     name: &'static str,
+/// }
+/// ```
+///
+/// And so is padding, whose bytes are uninitialized:
+///
+/// ```compile_fail
+/// #[derive(Clone, Copy, doors::DoorPayload)]
+/// #[repr(C)]
+/// struct Padded {
+///     small: u8,
+///     large: u64,
+/// }
+/// ```
+#[proc_macro_derive(DoorPayload)]
+pub fn door_payload(item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(item as DeriveInput);
+    let name = &input.ident;
 
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => {
+            return Error::new(
+                name.span(),
+                "DoorPayload can only be derived for structs",
+            )
+            .to_compile_error()
+            .into()
         }
+    };
+    if !input.generics.params.is_empty() {
+        return Error::new(
+            input.generics.span(),
+            "DoorPayload cannot be derived for generic structs",
+        )
+        .to_compile_error()
+        .into();
+    }
+    if !is_repr_c(&input.attrs) {
+        return Error::new(
+            name.span(),
+            "DoorPayload structs must be #[repr(C)], so that their layout is fixed",
+        )
+        .to_compile_error()
+        .into();
+    }
+    for field in fields {
+        if let Some(pointer) = find_pointer(&field.ty) {
+            return Error::new(
+                pointer.span(),
+                "pointers and references mean nothing in another process, \
+                 so they cannot be part of a DoorPayload",
+            )
+            .to_compile_error()
+            .into();
+        }
+    }
+
+    let types: Vec<&Type> = fields.iter().map(|field| &field.ty).collect();
+    let q = quote! {
+        const _: fn() = || {
+            fn assert_payload<T>()
+            where
+                T: doors::typed::AsBytes + doors::typed::FromBytes,
+            {
+            }
+            #( assert_payload::<#types>(); )*
+        };
+
+        const _: () = assert!(
+            std::mem::size_of::<#name>() == 0 #( + std::mem::size_of::<#types>() )*,
+            "DoorPayload structs must not have padding",
+        );
 
+        // Safety: repr(C), made only of payloads, and without padding, all of
+        // which is checked above.
+        unsafe impl doors::typed::AsBytes for #name {}
+        unsafe impl doors::typed::FromBytes for #name {}
     };
 
     TokenStream::from(q)